@@ -0,0 +1,121 @@
+//! Benchmarks of the solver on hard and easy puzzles, and of the generator.
+//!
+//! Run with `cargo bench`.
+
+use std::time::Duration;
+use std::time::Instant;
+use sudoku_solver_in_rust::dlx::solve_dlx;
+use sudoku_solver_in_rust::format::parse_sdm;
+use sudoku_solver_in_rust::generator::generate;
+use sudoku_solver_in_rust::generator::Symmetry;
+use sudoku_solver_in_rust::rng::Rng;
+use sudoku_solver_in_rust::solver::*;
+use sudoku_solver_in_rust::techniques::Techniques;
+
+/// Puzzles known to need a lot of guessing.
+const HARD: &str = "\
+800000000003600000070090200050007000000045700000100030001000068008500010090000400
+000000012000000003002300400001800005060070800000009000008500000900040500470006000
+000000039000001005003050800008090006070002000100400000009080050020000600400700000
+000000012000035000000600070700000300000400800100000000000120000080000040050000600
+100007090030020008009600500005300900010080002600004000300000010040000007007000300
+000000010400000000020000000000050407008000300001090000300400200050100000000806000
+";
+
+/// Puzzles that singles solve.
+const EASY: &str = "\
+010506003000004150050002096905000600062845310401609020800057000040030005590000870
+350009060170050008206010900702000005008360000030001096000700534507104009000596701
+620000000490020038030907000542096810006800940900004702059300401070509000000201050
+039701006480090157021400830800510000200900003040830000370620001600107002000049000
+406001200008340001010000400160020009289100300003009800650790104007010000041206907
+754000200006370100010260058900000002400907315130500689008000000203000867670000500
+";
+
+/// Runs `f` repeatedly for about a second and returns the mean time.
+fn measure<F: FnMut()>(mut f: F) -> Duration {
+    let start = Instant::now();
+    let mut runs = 0;
+    while runs == 0 || start.elapsed() < Duration::from_secs(1) {
+        f();
+        runs += 1;
+    }
+    start.elapsed() / runs
+}
+
+fn main() {
+    let puzzles = parse_sdm(HARD).unwrap();
+    for strategy in [Strategy::FewestCandidates, Strategy::MostConstrainedUnits] {
+        let options = Options {
+            strategy,
+            ..Options::default()
+        };
+        let time = measure(|| {
+            for puzzle in &puzzles {
+                for_each_solution_with(puzzle, &options, |_| ());
+            }
+        });
+        let guesses: usize = puzzles
+            .iter()
+            .map(|puzzle| for_each_solution_with_stats(puzzle, &options, |_| ()).guesses)
+            .sum();
+        println!("{:?}: {:?} per corpus, {} guesses", strategy, time, guesses);
+    }
+
+    let time = measure(|| {
+        for puzzle in &puzzles {
+            solve_dlx(puzzle, |_| ());
+        }
+    });
+    println!("Dancing links: {:?} per corpus", time);
+
+    let easy = parse_sdm(EASY).unwrap();
+    let all = Techniques {
+        claiming: true,
+        unique_rectangle: true,
+        remote_pairs: true,
+        finned_x_wing: true,
+        bug_plus_one: true,
+        als_xz: true,
+    };
+    for (name, techniques) in [("singles", Techniques::default()), ("all techniques", all)] {
+        let time = measure(|| {
+            for puzzle in &easy {
+                solve_logically(puzzle, &techniques);
+            }
+        });
+        println!("Easy with {}: {:?} per corpus", name, time);
+    }
+
+    // Solving many puzzles in a row, as `--batch` does, with and without
+    // reusing the solving boards.
+    let corpus = [puzzles.as_slice(), easy.as_slice()].concat().repeat(50);
+    let time = measure(|| {
+        for puzzle in &corpus {
+            solve_first(puzzle);
+        }
+    });
+    println!("First solutions of {}: {:?}", corpus.len(), time);
+    let mut scratch = Scratch::new();
+    let time = measure(|| {
+        for puzzle in &corpus {
+            scratch.solve_first(puzzle);
+        }
+    });
+    println!(
+        "First solutions of {} with a scratch: {:?}",
+        corpus.len(),
+        time
+    );
+
+    // Generating removes clues as long as the puzzle stays unique, so this
+    // mostly measures the uniqueness checks.
+    for symmetry in [Symmetry::None, Symmetry::Rotational180] {
+        let time = measure(|| {
+            for seed in 0..20 {
+                generate(0, symmetry, &mut Rng::new(seed));
+            }
+        });
+        println!("Generating 20 with {:?}: {:?}", symmetry, time);
+    }
+}
@@ -1,41 +1,149 @@
+use crate::error::SudokuError;
 use std::fmt::Display;
 use std::fmt::Error;
 use std::fmt::Formatter;
 
-/// The size (the length of a edge) of a block: 3.
-pub const N_BLOCK: usize = 3;
+/// The number of rows in a block: 3.
+#[cfg(not(any(board_4x4, board_6x6, board_16x16, board_25x25)))]
+pub const BLOCK_ROWS: usize = 3;
 
-/// The size (the length of a edge) of a board: 9.
-pub const N: usize = N_BLOCK * N_BLOCK;
+/// The number of columns in a block: 3.
+///
+/// For a board of rectangular blocks, change this and [`BLOCK_ROWS`], as the
+/// `board_6x6` configuration does with blocks of two rows and three columns.
+#[cfg(not(any(board_4x4, board_6x6, board_16x16, board_25x25)))]
+pub const BLOCK_COLS: usize = 3;
+
+// Building with `--cfg board_4x4`, `board_6x6`, `board_16x16`, or
+// `board_25x25` changes the board size, which `tests/board_4x4.rs`,
+// `tests/board_6x6.rs`, and `tests/board_large.rs` check.
+#[cfg(board_4x4)]
+pub const BLOCK_ROWS: usize = 2;
+#[cfg(board_4x4)]
+pub const BLOCK_COLS: usize = 2;
+#[cfg(board_6x6)]
+pub const BLOCK_ROWS: usize = 2;
+#[cfg(board_6x6)]
+pub const BLOCK_COLS: usize = 3;
+#[cfg(board_16x16)]
+pub const BLOCK_ROWS: usize = 4;
+#[cfg(board_16x16)]
+pub const BLOCK_COLS: usize = 4;
+#[cfg(board_25x25)]
+pub const BLOCK_ROWS: usize = 5;
+#[cfg(board_25x25)]
+pub const BLOCK_COLS: usize = 5;
+
+/// The size (the length of a edge) of a square block.
+#[deprecated(note = "use `BLOCK_ROWS` and `BLOCK_COLS`")]
+pub const N_BLOCK: usize = BLOCK_ROWS;
+
+/// The size (the length of an edge) of a board, which is 9 unless another
+/// size is configured.
+pub const N: usize = BLOCK_ROWS * BLOCK_COLS;
 
 /// Cell of an intermediate board used in solving.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct SolvingCell {
-    /// Possible values for this cell.
-    values: [bool; N],
+    /// Possible values for this cell, where bit `n` is set if the cell can be
+    /// `n`.
+    values: Values,
     /// Whether this cell's values have changed and filtering is pending.
     update: bool,
 }
 
+/// Serialized form of [`SolvingCell`]: the candidates in ascending order, the
+/// update flag, and whether the cell has a unique candidate. This form does
+/// not depend on how the candidates are stored in memory.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize, serde::Serialize)]
+#[serde(rename = "SolvingCell")]
+struct SerializedCell {
+    candidates: Vec<usize>,
+    update: bool,
+    unique: bool,
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SolvingCell {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedCell {
+            candidates: self.iter().collect(),
+            update: self.update,
+            unique: self.get_unique().is_some(),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SolvingCell {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let cell = SerializedCell::deserialize(deserializer)?;
+        let mut values = 0;
+        for &n in &cell.candidates {
+            if n >= N {
+                return Err(D::Error::custom(format!("invalid candidate {}", n)));
+            }
+            values |= 1 << n;
+        }
+        let result = SolvingCell {
+            values,
+            update: cell.update,
+        };
+        if result.get_unique().is_some() != cell.unique {
+            return Err(D::Error::custom("unique flag does not match candidates"));
+        }
+        Ok(result)
+    }
+}
+
+/// Bit set of the candidates of a [`SolvingCell`], which must have at least
+/// `N` bits. Boards up to 16x16 use 16 bits to keep the cells small.
+#[cfg(not(board_25x25))]
+type Values = u16;
+#[cfg(board_25x25)]
+type Values = u32;
+
+const _: () = assert!(
+    N <= Values::BITS as usize,
+    "the candidates do not fit in `Values`"
+);
+
 impl SolvingCell {
     /// Creates a new cell.
     pub fn new(v: Option<usize>) -> SolvingCell {
         match v {
             None => SolvingCell {
-                values: [true; N],
+                values: Values::MAX >> (Values::BITS as usize - N),
                 update: false,
             },
             Some(n) => SolvingCell {
-                values: {
-                    let mut values = [false; N];
-                    values[n] = true;
-                    values
-                },
+                values: 1 << n,
                 update: true,
             },
         }
     }
 
+    /// Creates a cell that can be any of `candidates`, with its update
+    /// pending so that propagation examines it.
+    ///
+    /// # Panics
+    ///
+    /// If a candidate is not less than [`N`].
+    pub fn from_candidates<I: IntoIterator<Item = usize>>(candidates: I) -> SolvingCell {
+        let mut values = 0;
+        for n in candidates {
+            assert!(n < N, "candidate {} is out of range", n);
+            values |= 1 << n;
+        }
+        SolvingCell {
+            values,
+            update: true,
+        }
+    }
+
     /// Whether this cell's value has changed and elimination is pending.
     pub fn has_update(&self) -> bool {
         self.update
@@ -48,51 +156,178 @@ impl SolvingCell {
 
     /// Whether this cell has possibility to be `n` in the solution.
     pub fn can_be(&self, n: usize) -> bool {
-        self.values[n]
+        self.values & (1 << n) != 0
     }
 
     /// Returns the number if `self` is unique.
     pub fn get_unique(&self) -> Option<usize> {
-        let mut i = self.iter();
-        let n = i.next();
-        if let Some(_) = n {
-            if let Some(_) = i.next() {
-                return None;
-            }
+        if self.values.is_power_of_two() {
+            Some(self.values.trailing_zeros() as usize)
+        } else {
+            None
         }
-        n
     }
 
     /// Number of possibilities in this cell.
     pub fn count(&self) -> usize {
-        self.iter().count()
+        self.values.count_ones() as usize
     }
 
     /// Iterates possibilities.
     pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
-        self.values
-            .iter()
-            .enumerate()
-            .filter_map(|(n, &b)| if b { Some(n) } else { None })
+        let mut values = self.values;
+        std::iter::from_fn(move || {
+            if values == 0 {
+                return None;
+            }
+            let n = values.trailing_zeros() as usize;
+            values &= values - 1;
+            Some(n)
+        })
+    }
+
+    /// Returns a cell with the possibilities common to `self` and `other`.
+    ///
+    /// The cells returned by the set operations have no pending update.
+    pub fn intersect(&self, other: &SolvingCell) -> SolvingCell {
+        SolvingCell {
+            values: self.values & other.values,
+            update: false,
+        }
+    }
+
+    /// Returns a cell with the possibilities in either `self` or `other`.
+    pub fn union(&self, other: &SolvingCell) -> SolvingCell {
+        SolvingCell {
+            values: self.values | other.values,
+            update: false,
+        }
+    }
+
+    /// Returns a cell with the possibilities in `self` but not in `other`.
+    pub fn difference(&self, other: &SolvingCell) -> SolvingCell {
+        SolvingCell {
+            values: self.values & !other.values,
+            update: false,
+        }
     }
 
     /// Remove the given possibility.
     /// Returns true if `n` was previously contained in `self`.
     pub fn remove(&mut self, n: usize) -> bool {
-        self.values[n] && {
-            self.values[n] = false;
+        self.can_be(n) && {
+            self.values &= !(1 << n);
             self.update = true;
             true
         }
     }
 }
 
-/// 9x9 collection of cells.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// Square collection of [`N`] by [`N`] cells.
+///
+/// Boards are ordered lexicographically in row-major order.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Board<T>(pub [[T; N]; N]);
 
+impl<T: Default> Default for Board<T> {
+    fn default() -> Board<T> {
+        Board(std::array::from_fn(|_| {
+            std::array::from_fn(|_| T::default())
+        }))
+    }
+}
+
+/// Iterates the rows of a board from top to bottom, so that
+/// `for row in &board` visits each `[T; N]`.
+impl<'a, T> IntoIterator for &'a Board<T> {
+    type Item = &'a [T; N];
+    type IntoIter = std::slice::Iter<'a, [T; N]>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<T> Board<T> {
+    /// Returns a board with `f` applied to each cell of `self`.
+    pub fn map<U, F>(&self, f: F) -> Board<U>
+    where
+        F: Fn(&T) -> U,
+    {
+        Board(std::array::from_fn(|i| {
+            std::array::from_fn(|j| f(&self.0[i][j]))
+        }))
+    }
+
+    /// Returns the cells for which `pred` holds as `(row, col)` in row-major
+    /// order.
+    ///
+    /// ```
+    /// # use sudoku_solver_in_rust::board::{Board, N};
+    /// # use sudoku_solver_in_rust::solver::propagate;
+    /// let problem = Board::empty().with_clue(0, 0, 1).with_clue(N - 1, N - 1, 2);
+    /// assert_eq!(problem.cells_where(Option::is_some), [(0, 0), (N - 1, N - 1)]);
+    /// assert_eq!(problem.cells_where(Option::is_none).len(), N * N - 2);
+    ///
+    /// let board = propagate(&problem);
+    /// let determined = board.cells_where(|cell| cell.count() == 1);
+    /// assert_eq!(determined, [(0, 0), (N - 1, N - 1)]);
+    /// ```
+    pub fn cells_where<P>(&self, pred: P) -> Vec<(usize, usize)>
+    where
+        P: Fn(&T) -> bool,
+    {
+        (0..N)
+            .flat_map(|i| (0..N).map(move |j| (i, j)))
+            .filter(|&(i, j)| pred(&self.0[i][j]))
+            .collect()
+    }
+
+    /// Swaps rows `a` and `b`.
+    ///
+    /// The rows must be in the same band, so that each block keeps its
+    /// cells and a valid grid stays valid.
+    ///
+    /// # Panics
+    ///
+    /// If `a` or `b` is not a row of the board, or they are in different
+    /// bands.
+    pub fn swap_rows(&mut self, a: usize, b: usize) {
+        assert!(
+            a < N && b < N && a / BLOCK_ROWS == b / BLOCK_ROWS,
+            "rows {} and {} are not in the same band",
+            a,
+            b
+        );
+        self.0.swap(a, b);
+    }
+
+    /// Swaps columns `a` and `b`.
+    ///
+    /// The columns must be in the same stack, so that each block keeps its
+    /// cells and a valid grid stays valid.
+    ///
+    /// # Panics
+    ///
+    /// If `a` or `b` is not a column of the board, or they are in different
+    /// stacks.
+    pub fn swap_cols(&mut self, a: usize, b: usize) {
+        assert!(
+            a < N && b < N && a / BLOCK_COLS == b / BLOCK_COLS,
+            "columns {} and {} are not in the same stack",
+            a,
+            b
+        );
+        for row in &mut self.0 {
+            row.swap(a, b);
+        }
+    }
+}
+
 impl Board<SolvingCell> {
     /// Convert to a final board if `self` is a valid solution.
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_solution(&self) -> Option<Board<usize>> {
         let mut solution = Board([[0; N]; N]);
         for i in 0..N {
@@ -104,9 +339,249 @@ impl Board<SolvingCell> {
     }
 }
 
-impl Display for Board<usize> {
+/// Panics unless `(i, j)` is a cell of a board.
+fn check_bounds(i: usize, j: usize) {
+    assert!(i < N && j < N, "cell ({}, {}) is out of the board", i, j);
+}
+
+impl Board<usize> {
+    /// Returns the value at row `i` and column `j` (both 0-based) as a number
+    /// from 1 to [`N`].
+    ///
+    /// # Panics
+    ///
+    /// `i` and `j` must be less than [`N`]; otherwise this function panics.
+    pub fn get(&self, i: usize, j: usize) -> usize {
+        check_bounds(i, j);
+        self.0[i][j] + 1
+    }
+
+    /// Formats `self` like [`Display`], but with values from `offset` to
+    /// `offset + N - 1` instead of from 1 to [`N`].
+    ///
+    /// ```
+    /// # use sudoku_solver_in_rust::board::{Board, N};
+    /// let solution = Board([[4; N]; N]);
+    /// assert!(solution.to_string_with(0).starts_with("4 4 4"));
+    /// assert!(solution.to_string().starts_with("5 5 5"));
+    /// ```
+    pub fn to_string_with(&self, offset: usize) -> String {
+        WithOffset(self, offset).to_string()
+    }
+
+    /// Returns the values of `unit` in the order of [`Unit::cells`].
+    fn unit_values(&self, unit: Unit) -> [usize; N] {
+        let mut values = [0; N];
+        for (value, (i, j)) in values.iter_mut().zip(unit.cells()) {
+            *value = self.0[i][j];
+        }
+        values
+    }
+
+    /// Returns the values of row `i`.
+    ///
+    /// # Panics
+    ///
+    /// `i` must be less than [`N`]; otherwise this function panics.
+    pub fn row(&self, i: usize) -> [usize; N] {
+        assert!(i < N, "row {} is out of the board", i);
+        self.0[i]
+    }
+
+    /// Returns the values of column `j`.
+    ///
+    /// # Panics
+    ///
+    /// `j` must be less than [`N`]; otherwise this function panics.
+    pub fn col(&self, j: usize) -> [usize; N] {
+        assert!(j < N, "column {} is out of the board", j);
+        self.unit_values(Unit::Col(j))
+    }
+
+    /// Returns the values of the block at row `bi` and column `bj` of blocks,
+    /// in row-major order.
+    ///
+    /// ```
+    /// # use sudoku_solver_in_rust::board::{Board, BLOCK_COLS, BLOCK_ROWS, N};
+    /// let mut solution = Board([[0; N]; N]);
+    /// solution.0[BLOCK_ROWS][BLOCK_COLS + 1] = 3;
+    /// let block = solution.block(1, 1);
+    /// assert_eq!(block[1], 3);
+    /// assert_eq!(block.iter().sum::<usize>(), 3);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// `bi` must be less than `N / BLOCK_ROWS` and `bj` less than
+    /// `N / BLOCK_COLS`; otherwise this function panics.
+    pub fn block(&self, bi: usize, bj: usize) -> [usize; N] {
+        assert!(
+            bi < N / BLOCK_ROWS && bj < N / BLOCK_COLS,
+            "block ({}, {}) is out of the board",
+            bi,
+            bj
+        );
+        self.unit_values(Unit::Block(bi, bj))
+    }
+
+    /// Whether `self` is a solved grid, that is, every row, column, and block
+    /// has each value from 0 to `N - 1` exactly once.
+    pub fn is_valid_complete(&self) -> bool {
+        all_units().all(|unit| {
+            let mut seen = [false; N];
+            unit.cells().all(|(i, j)| {
+                let n = self.0[i][j];
+                n < N && !std::mem::replace(&mut seen[n], true)
+            })
+        })
+    }
+
+    /// Returns the cells where `self` and `other` differ as the row, column,
+    /// value in `self`, and value in `other`, all 0-based, in row-major order.
+    pub fn diff(&self, other: &Board<usize>) -> Vec<(usize, usize, usize, usize)> {
+        (0..N * N)
+            .map(|k| (k / N, k % N))
+            .filter(|&(i, j)| self.0[i][j] != other.0[i][j])
+            .map(|(i, j)| (i, j, self.0[i][j], other.0[i][j]))
+            .collect()
+    }
+}
+
+impl Board<Option<usize>> {
+    /// Creates a puzzle with no clues.
+    pub fn empty() -> Board<Option<usize>> {
+        Board::default()
+    }
+
+    /// Returns `self` with the clue at row `i` and column `j` (both 0-based)
+    /// set to `value`, a number from 1 to [`N`].
+    ///
+    /// ```
+    /// # use sudoku_solver_in_rust::board::{Board, N};
+    /// let problem = Board::empty().with_clue(0, 0, 1).with_clue(N - 1, N - 1, N);
+    /// assert_eq!(problem.get(0, 0), Some(1));
+    /// assert_eq!(problem.get(N - 1, N - 1), Some(N));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// `i` and `j` must be less than [`N`] and `value` must be from 1 to [`N`];
+    /// otherwise this function panics.
+    pub fn with_clue(mut self, i: usize, j: usize, value: usize) -> Board<Option<usize>> {
+        check_bounds(i, j);
+        assert!((1..=N).contains(&value), "invalid clue {}", value);
+        self.0[i][j] = Some(value - 1);
+        self
+    }
+
+    /// Returns the given at row `i` and column `j` (both 0-based) as a number
+    /// from 1 to [`N`], or `None` if the cell is empty.
+    ///
+    /// # Panics
+    ///
+    /// `i` and `j` must be less than [`N`]; otherwise this function panics.
+    pub fn get(&self, i: usize, j: usize) -> Option<usize> {
+        check_bounds(i, j);
+        self.0[i][j].map(|n| n + 1)
+    }
+
+    /// Returns the cells filled in either `self` or `other`.
+    ///
+    /// If the two have different values in the same cell, the first such
+    /// cell in row-major order is returned as the error, 0-based.
+    pub fn merge(
+        &self,
+        other: &Board<Option<usize>>,
+    ) -> Result<Board<Option<usize>>, (usize, usize)> {
+        let mut merged = *self;
+        for i in 0..N {
+            for j in 0..N {
+                match (self.0[i][j], other.0[i][j]) {
+                    (Some(a), Some(b)) if a != b => return Err((i, j)),
+                    (None, b) => merged.0[i][j] = b,
+                    _ => (),
+                }
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Fills the empty `cells` with their values in `solution`, in the order
+    /// listed, as when revealing a solution step by step.
+    ///
+    /// If a listed cell is not empty, or its value repeats in the row, column,
+    /// or block, the first such cell is returned as the error, 0-based, and
+    /// `self` is left unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Every cell must be on the board; otherwise this function panics.
+    pub fn fill_from(
+        &mut self,
+        solution: &Board<usize>,
+        cells: &[(usize, usize)],
+    ) -> Result<(), (usize, usize)> {
+        let mut filled = *self;
+        for &(i, j) in cells {
+            check_bounds(i, j);
+            let n = solution.0[i][j];
+            let repeated = Unit::Row(i)
+                .cells()
+                .chain(Unit::Col(j).cells())
+                .chain(Unit::block_of(i, j).cells())
+                .any(|(i2, j2)| filled.0[i2][j2] == Some(n));
+            if filled.0[i][j].is_some() || repeated {
+                return Err((i, j));
+            }
+            filled.0[i][j] = Some(n);
+        }
+        *self = filled;
+        Ok(())
+    }
+}
+
+impl From<[[u8; N]; N]> for Board<Option<usize>> {
+    /// Creates a puzzle from rows of numbers from 1 to [`N`], where 0 is an
+    /// empty cell.
+    ///
+    /// ```
+    /// # use sudoku_solver_in_rust::board::{Board, N};
+    /// let mut rows = [[0; N]; N];
+    /// rows[0][0] = 1;
+    /// rows[N - 1][N - 1] = N as u8;
+    /// let problem = Board::from(rows);
+    /// assert_eq!(problem.get(0, 0), Some(1));
+    /// assert_eq!(problem.get(0, 2), None);
+    /// assert_eq!(problem.get(N - 1, N - 1), Some(N));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function panics if any number is greater than [`N`].
+    fn from(rows: [[u8; N]; N]) -> Self {
+        Board(rows.map(|row| {
+            row.map(|n| match n as usize {
+                0 => None,
+                n if n <= N => Some(n - 1),
+                n => panic!("invalid clue {}", n),
+            })
+        }))
+    }
+}
+
+impl From<Board<usize>> for [[u8; N]; N] {
+    /// Converts a solution to rows of numbers from 1 to [`N`].
+    fn from(board: Board<usize>) -> Self {
+        board.0.map(|row| row.map(|n| n as u8 + 1))
+    }
+}
+
+/// Solution displayed with `offset` added to each value.
+struct WithOffset<'a>(&'a Board<usize>, usize);
+
+impl Display for WithOffset<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        for line in &self.0 {
+        for line in &(self.0).0 {
             let mut first = true;
             for cell in line {
                 if first {
@@ -114,7 +589,30 @@ impl Display for Board<usize> {
                 } else {
                     f.write_str(" ")?;
                 }
-                f.write_fmt(format_args!("{}", cell + 1))?;
+                f.write_fmt(format_args!("{}", cell + self.1))?;
+            }
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Board<usize> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        WithOffset(self, 1).fmt(f)
+    }
+}
+
+/// Prints a puzzle in the same form as a solution, with `0` for the empty
+/// cells, which [`read_problem`](crate::format::read_problem) reads back.
+impl Display for Board<Option<usize>> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        for line in self {
+            for (j, cell) in line.iter().enumerate() {
+                if j > 0 {
+                    f.write_str(" ")?;
+                }
+                f.write_fmt(format_args!("{}", cell.map_or(0, |n| n + 1)))?;
             }
             f.write_str("\n")?;
         }
@@ -122,34 +620,292 @@ impl Display for Board<usize> {
     }
 }
 
+/// Prints a unit with 1-based numbers, such as `row 3` or `block 2`, where the
+/// blocks are numbered in row-major order.
+impl Display for Unit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match *self {
+            Unit::Row(i) => write!(f, "row {}", i + 1),
+            Unit::Col(j) => write!(f, "column {}", j + 1),
+            Unit::Block(bi, bj) => write!(f, "block {}", bi * (N / BLOCK_COLS) + bj + 1),
+            Unit::Diagonal(false) => f.write_str("the main diagonal"),
+            Unit::Diagonal(true) => f.write_str("the anti-diagonal"),
+        }
+    }
+}
+
+/// Returns a mask that is true at the clues of `problem`.
+pub fn clue_mask(problem: &Board<Option<usize>>) -> Board<bool> {
+    problem.map(Option::is_some)
+}
+
+/// Returns a puzzle having the values of `full` as clues where `mask` is true.
+pub fn apply_mask(full: &Board<usize>, mask: &Board<bool>) -> Board<Option<usize>> {
+    let mut problem = Board([[None; N]; N]);
+    for i in 0..N {
+        for j in 0..N {
+            if mask.0[i][j] {
+                problem.0[i][j] = Some(full.0[i][j]);
+            }
+        }
+    }
+    problem
+}
+
+/// Whether `a` and `b` have the same candidates in each cell, regardless of
+/// the order in which they are listed and of repetitions.
+///
+/// This compares candidate grids written as lists, such as the expected
+/// candidates in a test, which `==` would compare in order.
+pub fn candidates_equal(a: &Board<Vec<usize>>, b: &Board<Vec<usize>>) -> bool {
+    let as_set = |candidates: &Vec<usize>| {
+        let mut set = candidates.clone();
+        set.sort_unstable();
+        set.dedup();
+        set
+    };
+    (0..N * N).all(|k| as_set(&a.0[k / N][k % N]) == as_set(&b.0[k / N][k % N]))
+}
+
+/// Group of cells that must contain each value exactly once.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Unit {
+    /// Row `i`.
+    Row(usize),
+    /// Column `j`.
+    Col(usize),
+    /// Block at row `bi` and column `bj` of blocks, each from 0 to 2. In
+    /// general, `bi` is less than `N / BLOCK_ROWS` and `bj` is less than
+    /// `N / BLOCK_COLS`.
+    Block(usize, usize),
+    /// Main diagonal from the top-left corner if false; anti-diagonal from
+    /// the top-right corner if true. Only diagonal variants use these.
+    Diagonal(bool),
+}
+
+impl Unit {
+    /// The block containing cell `(i, j)`.
+    pub fn block_of(i: usize, j: usize) -> Unit {
+        Unit::Block(i / BLOCK_ROWS, j / BLOCK_COLS)
+    }
+
+    /// The `k`-th cell of this unit.
+    fn cell(self, k: usize) -> (usize, usize) {
+        match self {
+            Unit::Row(i) => (i, k),
+            Unit::Col(j) => (k, j),
+            Unit::Block(bi, bj) => (
+                bi * BLOCK_ROWS + k / BLOCK_COLS,
+                bj * BLOCK_COLS + k % BLOCK_COLS,
+            ),
+            Unit::Diagonal(false) => (k, k),
+            Unit::Diagonal(true) => (k, N - 1 - k),
+        }
+    }
+
+    /// Iterator of cells in this unit.
+    pub fn cells(self) -> impl Iterator<Item = (usize, usize)> + Clone {
+        let cells: [(usize, usize); N] = std::array::from_fn(|k| self.cell(k));
+        IntoIterator::into_iter(cells)
+    }
+}
+
+/// Group of cells that must contain each value exactly once in addition to
+/// the rows, columns, and blocks, used by variants like Windoku.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Region([(usize, usize); N]);
+
+impl Region {
+    /// Creates a region of `cells`, which must be [`N`] distinct cells on the
+    /// board.
+    pub fn new(cells: &[(usize, usize)]) -> Result<Region, SudokuError> {
+        let mut region = [(0, 0); N];
+        if cells.len() != N {
+            return Err(SudokuError::InvalidRegion);
+        }
+        for (k, &(i, j)) in cells.iter().enumerate() {
+            if i >= N || j >= N || cells[..k].contains(&(i, j)) {
+                return Err(SudokuError::InvalidRegion);
+            }
+            region[k] = (i, j);
+        }
+        Ok(Region(region))
+    }
+
+    /// The four extra regions of Windoku, which are the 3x3 squares whose
+    /// top-left cells are at `(1, 1)`, `(1, 5)`, `(5, 1)`, and `(5, 5)`.
+    ///
+    /// Windoku is played on the 9x9 board only, so this is not defined for
+    /// the other sizes.
+    #[cfg(not(any(board_4x4, board_6x6, board_16x16, board_25x25)))]
+    pub fn windoku() -> [Region; 4] {
+        [(1, 1), (1, 5), (5, 1), (5, 5)].map(|(i, j)| {
+            Region(std::array::from_fn(|k| {
+                (i + k / BLOCK_COLS, j + k % BLOCK_COLS)
+            }))
+        })
+    }
+
+    /// Iterator of cells in this region.
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize)> + Clone {
+        IntoIterator::into_iter(self.0)
+    }
+
+    /// Whether this region contains cell `(i, j)`.
+    pub fn contains(&self, i: usize, j: usize) -> bool {
+        self.0.contains(&(i, j))
+    }
+}
+
+impl From<Unit> for Region {
+    /// The region of the cells of `unit`, so that units and extra regions can
+    /// be treated alike.
+    fn from(unit: Unit) -> Region {
+        Region(std::array::from_fn(|k| unit.cell(k)))
+    }
+}
+
+/// Inequality between the values of two cells, as in comparison Sudoku.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Comparison {
+    /// One of the cells, as `(row, col)`.
+    pub a: (usize, usize),
+    /// The other cell, as `(row, col)`.
+    pub b: (usize, usize),
+    /// Whether `a` has the greater value. Otherwise, `b` has.
+    pub a_greater: bool,
+}
+
+impl Comparison {
+    /// Returns the cell with the greater value and the one with the lesser.
+    pub fn greater_and_lesser(&self) -> ((usize, usize), (usize, usize)) {
+        if self.a_greater {
+            (self.a, self.b)
+        } else {
+            (self.b, self.a)
+        }
+    }
+
+    /// Whether `board` satisfies this comparison.
+    pub fn holds(&self, board: &Board<usize>) -> bool {
+        let ((i1, j1), (i2, j2)) = self.greater_and_lesser();
+        board.0[i1][j1] > board.0[i2][j2]
+    }
+}
+
+/// Group of cells whose values are distinct and add up to `sum`, as in killer
+/// Sudoku.
+///
+/// The sum is of the values as written, from 1 to [`N`], not of the 0-based
+/// values of [`Board<usize>`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Cage {
+    /// Cells of the cage, as `(row, col)`.
+    pub cells: Vec<(usize, usize)>,
+    /// Sum of the values in the cage.
+    pub sum: usize,
+}
+
+impl Cage {
+    /// Whether `board` satisfies this cage.
+    pub fn holds(&self, board: &Board<usize>) -> bool {
+        let values = self.cells.iter().map(|&(i, j)| board.0[i][j]);
+        let distinct = values
+            .clone()
+            .enumerate()
+            .all(|(k, n)| values.clone().take(k).all(|m| m != n));
+        distinct && values.map(|n| n + 1).sum::<usize>() == self.sum
+    }
+}
+
+/// Iterates the rows, columns, and blocks, in this order.
+///
+/// Each unit's cells are given by [`Unit::cells`], so a technique can treat
+/// all units alike. The extra regions of variants like Windoku are not
+/// included since they are not part of the board; use
+/// [`Options::units`](crate::solver::Options::units) to iterate them with the
+/// units.
+///
+/// ```
+/// # use sudoku_solver_in_rust::board::{all_units, Board, N};
+/// // Every row is the same, so only the rows have all values.
+/// let grid = Board([std::array::from_fn(|j| j); N]);
+/// let complete = all_units().filter(|unit| {
+///     let mut values: Vec<usize> = unit.cells().map(|(i, j)| grid.0[i][j]).collect();
+///     values.sort_unstable();
+///     values == (0..N).collect::<Vec<_>>()
+/// });
+/// assert_eq!(complete.count(), N);
+/// ```
+pub fn all_units() -> impl Iterator<Item = Unit> {
+    (0..N)
+        .map(Unit::Row)
+        .chain((0..N).map(Unit::Col))
+        .chain((0..N).map(|k| Unit::Block(k / (N / BLOCK_COLS), k % (N / BLOCK_COLS))))
+}
+
 /// Iterator of cells in a row.
 pub fn row_iter(i: usize) -> impl Iterator<Item = (usize, usize)> + Clone {
-    (0..N).map(move |j| (i, j))
+    Unit::Row(i).cells()
 }
 
 /// Iterator of cells in a column.
 pub fn col_iter(j: usize) -> impl Iterator<Item = (usize, usize)> + Clone {
-    (0..N).map(move |i| (i, j))
+    Unit::Col(j).cells()
 }
 
 /// Iterator of cells in a block.
 ///
 /// # Panics
 ///
-/// `i` and `j` must be 0, 3 or 6; otherwise this function panics.
+/// `i` must be a multiple of [`BLOCK_ROWS`] and `j` a multiple of
+/// [`BLOCK_COLS`], both less than [`N`]; otherwise this function panics.
 pub fn block_iter(i: usize, j: usize) -> impl Iterator<Item = (usize, usize)> + Clone {
-    assert_eq!(i % N_BLOCK, 0);
-    assert_eq!(j % N_BLOCK, 0);
-    assert!(i / N_BLOCK < N_BLOCK);
-    assert!(j / N_BLOCK < N_BLOCK);
-    (0..N).map(move |n| (i + n / N_BLOCK, j + n % N_BLOCK))
+    assert_eq!(i % BLOCK_ROWS, 0);
+    assert_eq!(j % BLOCK_COLS, 0);
+    assert!(i < N);
+    assert!(j < N);
+    Unit::block_of(i, j).cells()
 }
 
-#[cfg(test)]
+/// Like [`block_iter`], but returns `None` instead of panicking if `(i, j)`
+/// is not the top-left cell of a block.
+pub fn checked_block_iter(
+    i: usize,
+    j: usize,
+) -> Option<impl Iterator<Item = (usize, usize)> + Clone> {
+    if i.is_multiple_of(BLOCK_ROWS) && j.is_multiple_of(BLOCK_COLS) && i < N && j < N {
+        Some(Unit::block_of(i, j).cells())
+    } else {
+        None
+    }
+}
+
+/// Returns the top-left cell of the block containing `(i, j)`, which can be
+/// passed to [`block_iter`].
+///
+/// ```
+/// # use sudoku_solver_in_rust::board::{block_origin, BLOCK_COLS, BLOCK_ROWS, N};
+/// assert_eq!(block_origin(BLOCK_ROWS + 1, N - 1), (BLOCK_ROWS, N - BLOCK_COLS));
+/// ```
+pub fn block_origin(i: usize, j: usize) -> (usize, usize) {
+    (i / BLOCK_ROWS * BLOCK_ROWS, j / BLOCK_COLS * BLOCK_COLS)
+}
+
+// The tests are written for the 9x9 board.
+#[cfg(all(test, not(any(board_4x4, board_6x6, board_16x16, board_25x25))))]
 mod tests {
 
     use super::*;
 
+    /// A valid grid whose rows are the first row shifted, by a block within a
+    /// band and by one more from band to band.
+    fn valid_grid() -> Board<usize> {
+        Board(std::array::from_fn(|i| {
+            std::array::from_fn(|j| (i * BLOCK_COLS + i / BLOCK_ROWS + j) % N)
+        }))
+    }
+
     #[test]
     fn solving_cell_new_none() {
         let none = SolvingCell::new(None);
@@ -174,6 +930,44 @@ mod tests {
         assert!(!some.can_be(N - 1));
     }
 
+    #[test]
+    fn solving_cell_from_candidates() {
+        let cell = SolvingCell::from_candidates(vec![6, 1, 6]);
+        assert_eq!(cell.iter().collect::<Vec<usize>>(), vec![1, 6]);
+        assert!(cell.has_update());
+        let empty = SolvingCell::from_candidates(None);
+        assert_eq!(empty.count(), 0);
+        assert!(empty.has_update());
+        assert_eq!(
+            SolvingCell::from_candidates(0..N).iter().count(),
+            SolvingCell::new(None).count()
+        );
+    }
+
+    #[test]
+    fn solving_cell_set_operations() {
+        let cell = |values: &[usize]| {
+            let mut cell = SolvingCell::new(None);
+            for n in 0..N {
+                if !values.contains(&n) {
+                    cell.remove(n);
+                }
+            }
+            cell
+        };
+        let values = |cell: SolvingCell| cell.iter().collect::<Vec<usize>>();
+        let a = cell(&[0, 2, 4, 8]);
+        let b = cell(&[2, 3, 4]);
+
+        assert_eq!(values(a.intersect(&b)), vec![2, 4]);
+        assert_eq!(values(a.union(&b)), vec![0, 2, 3, 4, 8]);
+        assert_eq!(values(a.difference(&b)), vec![0, 8]);
+        assert_eq!(values(b.difference(&a)), vec![3]);
+        assert_eq!(a.difference(&a).count(), 0);
+        assert_eq!(a.union(&b), b.union(&a));
+        assert!(!a.intersect(&b).has_update());
+    }
+
     #[test]
     fn solving_cell_acknowledge() {
         let mut cell = SolvingCell::new(Some(6));
@@ -210,6 +1004,158 @@ mod tests {
         assert_eq!(cell.iter().next(), None);
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn solving_cell_serde() {
+        let mut cell = SolvingCell::new(None);
+        cell.remove(1);
+        cell.remove(2);
+        let json = serde_json::to_string(&cell).unwrap();
+        assert_eq!(
+            json,
+            r#"{"candidates":[0,3,4,5,6,7,8],"update":true,"unique":false}"#
+        );
+        assert_eq!(serde_json::from_str::<SolvingCell>(&json).unwrap(), cell);
+
+        let board = Board([[SolvingCell::new(Some(4)); N]; N]);
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Board<SolvingCell>>(&json).unwrap(),
+            board
+        );
+
+        let invalid = r#"{"candidates":[9],"update":false,"unique":true}"#;
+        assert!(serde_json::from_str::<SolvingCell>(invalid).is_err());
+        let mismatch = r#"{"candidates":[1,2],"update":false,"unique":true}"#;
+        assert!(serde_json::from_str::<SolvingCell>(mismatch).is_err());
+    }
+
+    #[test]
+    fn board_map() {
+        let board = Board::empty().with_clue(2, 5, 4);
+        let mapped = board.map(|&v| SolvingCell::new(v));
+        assert_eq!(mapped.0[0][0], SolvingCell::new(None));
+        assert_eq!(mapped.0[2][5], SolvingCell::new(Some(3)));
+        assert_eq!(mapped.map(|c| c.count()).0[2][5], 1);
+    }
+
+    #[test]
+    fn board_get() {
+        let mut solution = Board([[0; N]; N]);
+        solution.0[3][8] = 8;
+        assert_eq!(solution.get(3, 8), 9);
+        assert_eq!(solution.get(0, 0), 1);
+
+        let problem = Board::empty().with_clue(8, 1, 3);
+        assert_eq!(problem.0[8][1], Some(2));
+        assert_eq!(problem.get(8, 1), Some(3));
+        assert_eq!(problem.get(1, 8), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of the board")]
+    fn board_get_out_of_bounds() {
+        Board([[0; N]; N]).get(0, N);
+    }
+
+    #[test]
+    fn board_rows() {
+        let board = Board::empty().with_clue(0, 0, 5).with_clue(2, 1, 3);
+        let mut rows = 0;
+        for (i, row) in (&board).into_iter().enumerate() {
+            assert_eq!(row, &board.0[i]);
+            rows += 1;
+        }
+        assert_eq!(rows, N);
+        let clues: Vec<usize> = (&board).into_iter().flatten().flatten().copied().collect();
+        assert_eq!(clues, [4, 2]);
+    }
+
+    #[test]
+    fn comparing_candidates() {
+        let full = SolvingCell::new(None);
+        let grid = Board([[full; N]; N]).map(|c| c.iter().collect::<Vec<_>>());
+        let mut shuffled = grid.clone();
+        shuffled.0[2][3].reverse();
+        shuffled.0[5][0].push(4);
+        assert_ne!(shuffled, grid);
+        assert!(candidates_equal(&shuffled, &grid));
+        assert!(candidates_equal(&grid, &shuffled));
+
+        shuffled.0[8][8].retain(|&n| n != 0);
+        assert!(!candidates_equal(&shuffled, &grid));
+        assert!(!candidates_equal(&grid, &shuffled));
+    }
+
+    #[test]
+    fn diff_of_grids() {
+        let a = valid_grid();
+        assert_eq!(a.diff(&a), []);
+
+        let mut b = a;
+        b.0[0][3] = 7;
+        b.swap_rows(4, 5);
+        b.0[8][8] = 0;
+        assert_eq!(a.diff(&b).len(), 2 * N + 1 + 1);
+        let diff = b.diff(&a);
+        assert_eq!(diff[0], (0, 3, 7, 3));
+        assert_eq!(diff.last(), Some(&(8, 8, 0, a.0[8][8])));
+        assert!(diff
+            .iter()
+            .all(|&(i, j, x, y)| b.0[i][j] == x && a.0[i][j] == y));
+    }
+
+    #[test]
+    fn board_default() {
+        assert_eq!(Board::<usize>::default(), Board([[0; N]; N]));
+        assert_eq!(Board::empty(), Board([[None; N]; N]));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid clue")]
+    fn with_clue_zero() {
+        Board::empty().with_clue(0, 0, 0);
+    }
+
+    #[test]
+    fn from_u8_arrays() {
+        let mut rows = [[0; N]; N];
+        rows[0][0] = 1;
+        rows[8][8] = 9;
+        let board = Board::from(rows);
+        assert_eq!(board, Board::empty().with_clue(0, 0, 1).with_clue(8, 8, 9));
+
+        let mut solution = Board([[0; N]; N]);
+        solution.0[4][5] = 8;
+        let rows = <[[u8; N]; N]>::from(solution);
+        assert_eq!(rows[0][0], 1);
+        assert_eq!(rows[4][5], 9);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid clue 10")]
+    fn from_u8_arrays_out_of_range() {
+        let mut rows = [[0; N]; N];
+        rows[3][3] = 10;
+        let _ = Board::from(rows);
+    }
+
+    #[test]
+    fn masks() {
+        let mut full = Board([[0; N]; N]);
+        full.0[1][2] = 5;
+        full.0[7][7] = 8;
+        let mut mask = Board([[false; N]; N]);
+        mask.0[1][2] = true;
+        mask.0[4][4] = true;
+
+        let problem = apply_mask(&full, &mask);
+        assert_eq!(problem.0[1][2], Some(5));
+        assert_eq!(problem.0[4][4], Some(0));
+        assert_eq!(problem.0[7][7], None);
+        assert_eq!(clue_mask(&problem), mask);
+    }
+
     #[test]
     fn row_iter_values() {
         assert_eq!(
@@ -277,4 +1223,236 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn region_validation() {
+        let cells: Vec<_> = row_iter(2).collect();
+        let region = Region::new(&cells).unwrap();
+        assert!(region.cells().eq(cells.iter().copied()));
+        assert!(region.contains(2, 8));
+        assert!(!region.contains(3, 8));
+
+        assert_eq!(Region::new(&cells[1..]), Err(SudokuError::InvalidRegion));
+        let mut duplicate = cells.clone();
+        duplicate[0] = duplicate[1];
+        assert_eq!(Region::new(&duplicate), Err(SudokuError::InvalidRegion));
+        let mut outside = cells;
+        outside[0] = (9, 0);
+        assert_eq!(Region::new(&outside), Err(SudokuError::InvalidRegion));
+    }
+
+    #[test]
+    fn windoku_regions() {
+        let windows = Region::windoku();
+        assert!(windows[0].contains(1, 1) && windows[0].contains(3, 3));
+        assert!(windows[3].contains(7, 7));
+        for window in &windows {
+            Region::new(&window.cells().collect::<Vec<_>>()).unwrap();
+        }
+    }
+
+    #[test]
+    fn merge_boards() {
+        let givens = Board::empty().with_clue(0, 0, 5).with_clue(4, 4, 1);
+        let entries = Board::empty().with_clue(0, 0, 5).with_clue(8, 8, 2);
+        let merged = givens.merge(&entries).unwrap();
+        assert_eq!(merged, givens.with_clue(8, 8, 2));
+        assert_eq!(entries.merge(&givens), Ok(merged));
+        assert_eq!(givens.merge(&Board::empty()), Ok(givens));
+
+        let conflicting = entries.with_clue(4, 4, 3).with_clue(6, 0, 4);
+        assert_eq!(givens.with_clue(6, 0, 1).merge(&conflicting), Err((4, 4)));
+    }
+
+    #[test]
+    fn valid_complete_grids() {
+        let grid = valid_grid();
+        assert!(grid.is_valid_complete());
+
+        // The row still has every value, but two columns and blocks do not.
+        let mut swapped = grid;
+        swapped.0[0].swap(0, BLOCK_COLS);
+        assert!(!swapped.is_valid_complete());
+
+        // Every band is the first band, so only the columns repeat values.
+        let bands = Board(std::array::from_fn(|i| grid.0[i % BLOCK_ROWS]));
+        let complete = |mut values: [usize; N]| {
+            values.sort_unstable();
+            values == std::array::from_fn(|n| n)
+        };
+        assert!((0..N).all(|i| complete(bands.row(i))));
+        assert!((0..N).all(|k| complete(bands.block(k / BLOCK_ROWS, k % BLOCK_ROWS))));
+        assert!(!complete(bands.col(0)));
+        assert!(!bands.is_valid_complete());
+
+        // A Latin square whose blocks repeat values.
+        let latin = Board(std::array::from_fn(|i| {
+            std::array::from_fn(|j| (i + j) % N)
+        }));
+        assert!(!latin.is_valid_complete());
+
+        let mut out_of_range = grid;
+        out_of_range.0[4][4] = N;
+        assert!(!out_of_range.is_valid_complete());
+    }
+
+    #[test]
+    fn filling_from_solution() {
+        let solution = valid_grid();
+        assert!(solution.is_valid_complete());
+        let mut problem = Board::empty().with_clue(0, 0, 1);
+        problem.fill_from(&solution, &[(0, 1), (4, 4)]).unwrap();
+        assert_eq!(problem.get(0, 1), Some(2));
+        assert_eq!(problem.get(4, 4), Some(solution.get(4, 4)));
+        assert_eq!(problem.cells_where(|c| c.is_some()).len(), 3);
+
+        // A given cell, and a value that the board already has elsewhere.
+        let before = problem;
+        assert_eq!(problem.fill_from(&solution, &[(1, 1), (0, 0)]), Err((0, 0)));
+        let mut wrong = solution;
+        wrong.0[8][0] = 0;
+        assert_eq!(problem.fill_from(&wrong, &[(1, 1), (8, 0)]), Err((8, 0)));
+        assert_eq!(problem, before);
+    }
+
+    #[test]
+    fn unit_names() {
+        assert_eq!(Unit::Row(2).to_string(), "row 3");
+        assert_eq!(Unit::Col(0).to_string(), "column 1");
+        assert_eq!(Unit::Block(0, 1).to_string(), "block 2");
+        assert_eq!(Unit::block_of(8, 8).to_string(), "block 9");
+        assert_eq!(Unit::Diagonal(true).to_string(), "the anti-diagonal");
+    }
+
+    #[test]
+    fn unit_arrays() {
+        let grid = Board([
+            [0, 1, 2, 3, 4, 5, 6, 7, 8],
+            [3, 4, 5, 6, 7, 8, 0, 1, 2],
+            [6, 7, 8, 0, 1, 2, 3, 4, 5],
+            [1, 2, 0, 4, 5, 3, 7, 8, 6],
+            [4, 5, 3, 7, 8, 6, 1, 2, 0],
+            [7, 8, 6, 1, 2, 0, 4, 5, 3],
+            [2, 0, 1, 5, 3, 4, 8, 6, 7],
+            [5, 3, 4, 8, 6, 7, 2, 0, 1],
+            [8, 6, 7, 2, 0, 1, 5, 3, 4],
+        ]);
+        assert_eq!(grid.row(1), [3, 4, 5, 6, 7, 8, 0, 1, 2]);
+        assert_eq!(grid.col(1), [1, 4, 7, 2, 5, 8, 0, 3, 6]);
+        assert_eq!(grid.block(2, 1), [5, 3, 4, 8, 6, 7, 2, 0, 1]);
+        for k in 0..N {
+            let sorted = |mut values: [usize; N]| {
+                values.sort_unstable();
+                values
+            };
+            let all = std::array::from_fn(|n| n);
+            assert_eq!(sorted(grid.row(k)), all);
+            assert_eq!(sorted(grid.col(k)), all);
+            assert_eq!(sorted(grid.block(k / 3, k % 3)), all);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "block (3, 0) is out of the board")]
+    fn block_out_of_board() {
+        Board([[0; N]; N]).block(3, 0);
+    }
+
+    #[test]
+    fn swapping_lines() {
+        let grid = valid_grid();
+        assert!(grid.is_valid_complete());
+
+        let mut swapped = grid;
+        swapped.swap_rows(3, 5);
+        assert_eq!(swapped.0[3], grid.0[5]);
+        assert_eq!(swapped.0[5], grid.0[3]);
+        swapped.swap_cols(6, 8);
+        assert_eq!(swapped.0[0][6], 8);
+        assert!(swapped.is_valid_complete());
+
+        swapped.swap_cols(6, 8);
+        swapped.swap_rows(3, 5);
+        assert_eq!(swapped, grid);
+        swapped.swap_rows(4, 4);
+        assert_eq!(swapped, grid);
+    }
+
+    #[test]
+    #[should_panic(expected = "rows 2 and 3 are not in the same band")]
+    fn swap_rows_across_bands() {
+        Board::empty().swap_rows(2, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "columns 0 and 9 are not in the same stack")]
+    fn swap_cols_off_board() {
+        Board::empty().swap_cols(0, 9);
+    }
+
+    #[test]
+    fn blocks_partition_board() {
+        let mut seen = Board([[0; N]; N]);
+        for unit in all_units().filter(|u| matches!(u, Unit::Block(..))) {
+            for (i, j) in unit.cells() {
+                assert_eq!(Unit::block_of(i, j), unit);
+                seen.0[i][j] += 1;
+            }
+        }
+        assert_eq!(seen, Board([[1; N]; N]));
+    }
+
+    #[test]
+    fn checked_block_iter_origins() {
+        let cells: Vec<_> = checked_block_iter(3, 6).unwrap().collect();
+        assert_eq!(cells, block_iter(3, 6).collect::<Vec<_>>());
+        assert!(checked_block_iter(1, 0).is_none());
+        assert!(checked_block_iter(0, 4).is_none());
+        assert!(checked_block_iter(9, 0).is_none());
+
+        for i in 0..N {
+            for j in 0..N {
+                let (i0, j0) = block_origin(i, j);
+                assert!(block_iter(i0, j0).any(|cell| cell == (i, j)));
+            }
+        }
+    }
+
+    #[test]
+    fn cage_holds() {
+        let grid = valid_grid();
+        let cage = |cells: &[(usize, usize)], sum| Cage {
+            cells: cells.to_vec(),
+            sum,
+        };
+        assert!(cage(&[(0, 0), (0, 1)], 3).holds(&grid));
+        assert!(!cage(&[(0, 0), (0, 1)], 4).holds(&grid));
+        assert!(cage(&[(0, 0), (1, 0), (2, 0)], 12).holds(&grid));
+        // Two cells of the same value break the cage whatever the sum.
+        assert_eq!((grid.0[0][0], grid.0[1][6]), (0, 0));
+        assert!(!cage(&[(0, 0), (1, 6)], 2).holds(&grid));
+    }
+
+    #[test]
+    fn unit_cells() {
+        assert!(Unit::Row(3).cells().eq(row_iter(3)));
+        assert!(Unit::Col(7).cells().eq(col_iter(7)));
+        assert!(Unit::Block(2, 1).cells().eq(block_iter(6, 3)));
+        assert_eq!(Unit::block_of(7, 5), Unit::Block(2, 1));
+        assert!(Unit::Diagonal(false).cells().eq((0..N).map(|k| (k, k))));
+        assert_eq!(
+            Unit::Diagonal(true).cells().collect::<Vec<_>>(),
+            vec![
+                (0, 8),
+                (1, 7),
+                (2, 6),
+                (3, 5),
+                (4, 4),
+                (5, 3),
+                (6, 2),
+                (7, 1),
+                (8, 0)
+            ]
+        );
+    }
 }
@@ -0,0 +1,305 @@
+//! Alternative solver based on Knuth's Algorithm X with dancing links.
+//!
+//! Sudoku is encoded as an exact cover problem: each candidate placement of
+//! a value in a cell is a row that covers four columns, one for each of the
+//! constraints that the cell has a value, and that the row, column, and block
+//! have the value.
+
+use crate::board::*;
+use std::ops::ControlFlow;
+
+/// Number of exact cover columns.
+const COLUMNS: usize = 4 * N * N;
+
+/// Sparse exact cover matrix as circular doubly linked lists.
+///
+/// Node 0 is the root, nodes 1 to `COLUMNS` are the column headers, and the
+/// rest are the ones in the matrix.
+struct Matrix {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    /// Column header of each node.
+    column: Vec<usize>,
+    /// Matrix row of each node, which identifies a placement.
+    row: Vec<usize>,
+    /// Number of nodes in each column, indexed by column header.
+    size: Vec<usize>,
+}
+
+/// Placement of value `n` at `(i, j)` as a matrix row.
+fn row_of(i: usize, j: usize, n: usize) -> usize {
+    (i * N + j) * N + n
+}
+
+/// Column headers covered by the placement of value `n` at `(i, j)`.
+fn columns_of(i: usize, j: usize, n: usize) -> [usize; 4] {
+    let b = i / BLOCK_ROWS * (N / BLOCK_COLS) + j / BLOCK_COLS;
+    [
+        1 + i * N + j,
+        1 + N * N + i * N + n,
+        1 + 2 * N * N + j * N + n,
+        1 + 3 * N * N + b * N + n,
+    ]
+}
+
+impl Matrix {
+    fn new() -> Matrix {
+        let headers = COLUMNS + 1;
+        let mut m = Matrix {
+            left: (0..headers).map(|c| (c + headers - 1) % headers).collect(),
+            right: (0..headers).map(|c| (c + 1) % headers).collect(),
+            up: (0..headers).collect(),
+            down: (0..headers).collect(),
+            column: (0..headers).collect(),
+            row: vec![0; headers],
+            size: vec![0; headers],
+        };
+        for i in 0..N {
+            for j in 0..N {
+                for n in 0..N {
+                    m.add_row(row_of(i, j, n), &columns_of(i, j, n));
+                }
+            }
+        }
+        m
+    }
+
+    fn add_row(&mut self, row: usize, columns: &[usize]) {
+        let first = self.left.len();
+        for (k, &c) in columns.iter().enumerate() {
+            let x = first + k;
+            let last = (k + columns.len() - 1) % columns.len();
+            self.left.push(first + last);
+            self.right.push(first + (k + 1) % columns.len());
+            self.up.push(self.up[c]);
+            self.down.push(c);
+            self.down[self.up[c]] = x;
+            self.up[c] = x;
+            self.column.push(c);
+            self.row.push(row);
+            self.size[c] += 1;
+        }
+    }
+
+    fn cover(&mut self, c: usize) {
+        self.right[self.left[c]] = self.right[c];
+        self.left[self.right[c]] = self.left[c];
+        let mut i = self.down[c];
+        while i != c {
+            let mut j = self.right[i];
+            while j != i {
+                self.down[self.up[j]] = self.down[j];
+                self.up[self.down[j]] = self.up[j];
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    fn uncover(&mut self, c: usize) {
+        let mut i = self.up[c];
+        while i != c {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.column[j]] += 1;
+                self.down[self.up[j]] = j;
+                self.up[self.down[j]] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        self.right[self.left[c]] = c;
+        self.left[self.right[c]] = c;
+    }
+
+    /// Enumerates exact covers extending `rows`, calling `f` for each.
+    ///
+    /// Each level of the recursion adds one row to `rows`, which covers a cell
+    /// of the board, so the recursion nests only as deep as the number of
+    /// empty cells and the stack does not overflow however many covers there
+    /// are.
+    fn search<F>(&mut self, rows: &mut Vec<usize>, f: &mut F) -> ControlFlow<()>
+    where
+        F: FnMut(&[usize]) -> ControlFlow<()>,
+    {
+        if self.right[0] == 0 {
+            return f(rows);
+        }
+
+        // Choose the column with fewest rows.
+        let mut c = self.right[0];
+        let mut best = c;
+        while c != 0 {
+            if self.size[c] < self.size[best] {
+                best = c;
+            }
+            c = self.right[c];
+        }
+        let c = best;
+
+        self.cover(c);
+        let mut r = self.down[c];
+        while r != c {
+            rows.push(self.row[r]);
+            let mut j = self.right[r];
+            while j != r {
+                self.cover(self.column[j]);
+                j = self.right[j];
+            }
+
+            self.search(rows, f)?;
+
+            let mut j = self.left[r];
+            while j != r {
+                self.uncover(self.column[j]);
+                j = self.left[j];
+            }
+            rows.pop();
+            r = self.down[r];
+        }
+        self.uncover(c);
+        ControlFlow::Continue(())
+    }
+}
+
+/// Builds the matrix of `problem` with the rows of the givens selected, or
+/// returns `None` if the givens conflict.
+fn matrix_of(problem: &Board<Option<usize>>) -> Option<(Matrix, Vec<usize>)> {
+    let mut matrix = Matrix::new();
+    let mut covered = [false; COLUMNS + 1];
+    let mut rows = Vec::new();
+
+    // Select the rows of the givens.
+    for i in 0..N {
+        for j in 0..N {
+            if let Some(n) = problem.0[i][j] {
+                let columns = columns_of(i, j, n);
+                if columns.iter().any(|&c| covered[c]) {
+                    return None;
+                }
+                for &c in &columns {
+                    matrix.cover(c);
+                    covered[c] = true;
+                }
+                rows.push(row_of(i, j, n));
+            }
+        }
+    }
+    Some((matrix, rows))
+}
+
+/// Calls `f` for each solution of `problem`, like
+/// [`for_each_solution`](crate::solver::for_each_solution) but using dancing
+/// links instead of propagation and guessing.
+pub fn solve_dlx<F>(problem: &Board<Option<usize>>, mut f: F)
+where
+    F: FnMut(Board<usize>),
+{
+    let (mut matrix, mut rows) = match matrix_of(problem) {
+        Some(matrix) => matrix,
+        None => return,
+    };
+    let _ = matrix.search(&mut rows, &mut |rows| {
+        let mut solution = Board([[0; N]; N]);
+        for &r in rows {
+            solution.0[r / N / N][r / N % N] = r % N;
+        }
+        f(solution);
+        ControlFlow::Continue(())
+    });
+}
+
+/// Counts all solutions of `problem`.
+///
+/// Unlike [`count_solutions`](crate::solver::count_solutions), there is no
+/// limit, so this can take very long for a puzzle with few clues; an empty
+/// board has about 6.67 * 10^21 solutions, which is why the count is a `u128`
+/// rather than a `u64`.
+pub fn exact_solution_count(problem: &Board<Option<usize>>) -> u128 {
+    let (mut matrix, mut rows) = match matrix_of(problem) {
+        Some(matrix) => matrix,
+        None => return 0,
+    };
+    let mut count = 0;
+    let _ = matrix.search(&mut rows, &mut |_| {
+        count += 1;
+        ControlFlow::Continue(())
+    });
+    count
+}
+
+// The tests are written for the 9x9 board.
+#[cfg(all(test, not(any(board_4x4, board_6x6, board_16x16, board_25x25))))]
+mod tests {
+
+    use super::*;
+    use crate::format::parse_sdm;
+    use crate::solver::for_each_solution;
+    use crate::solver::solve_first;
+
+    fn solutions_of<S>(solve: S, problem: &Board<Option<usize>>) -> Vec<Board<usize>>
+    where
+        S: Fn(&Board<Option<usize>>, &mut dyn FnMut(Board<usize>)),
+    {
+        let mut solutions = Vec::new();
+        solve(problem, &mut |b| solutions.push(b));
+        solutions.sort();
+        solutions
+    }
+
+    fn dlx_first(problem: &Board<Option<usize>>) -> Board<usize> {
+        let mut solution = None;
+        solve_dlx(problem, |b| solution = Some(b));
+        solution.unwrap()
+    }
+
+    #[test]
+    fn dlx_agrees_with_solver() {
+        let puzzles = parse_sdm(
+            "000000010400000000020000000000050407008000300001090000300400200050100000000806000
+800000000003600000070090200050007000000045700000100030001000068008500010090000400",
+        )
+        .unwrap();
+        for problem in &puzzles {
+            let dlx = solutions_of(|p, f| solve_dlx(p, f), problem);
+            assert_eq!(dlx.len(), 1);
+            assert_eq!(dlx, solutions_of(|p, f| for_each_solution(p, f), problem));
+        }
+
+        let mut ambiguous = dlx_first(&puzzles[0]).map(|&n| Some(n));
+        ambiguous.0[0] = [None; N];
+        ambiguous.0[1] = [None; N];
+        let dlx = solutions_of(|p, f| solve_dlx(p, f), &ambiguous);
+        assert!(dlx.len() > 1);
+        assert_eq!(
+            dlx,
+            solutions_of(|p, f| for_each_solution(p, f), &ambiguous)
+        );
+    }
+
+    #[test]
+    fn dlx_conflicting_givens() {
+        let problem = Board::empty().with_clue(0, 0, 5).with_clue(8, 0, 5);
+        let mut found = false;
+        solve_dlx(&problem, |_| found = true);
+        assert!(!found);
+    }
+
+    #[test]
+    fn exact_count_agrees_with_solver() {
+        let mut problem = solve_first(&Board::empty()).unwrap().map(|&n| Some(n));
+        for row in &mut problem.0[..4] {
+            *row = [None; N];
+        }
+        let mut count = 0;
+        for_each_solution(&problem, |_| count += 1);
+        assert!(count > 1);
+        assert_eq!(exact_solution_count(&problem), count);
+
+        let conflicting = Board::empty().with_clue(0, 0, 5).with_clue(8, 0, 5);
+        assert_eq!(exact_solution_count(&conflicting), 0);
+    }
+}
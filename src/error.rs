@@ -0,0 +1,136 @@
+use crate::board::N;
+use std::fmt::Display;
+use std::fmt::Formatter;
+
+/// Error in reading or checking a puzzle.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SudokuError {
+    /// A character that is neither a digit nor an empty-cell placeholder.
+    /// Line and column numbers are 1-based.
+    InvalidCell {
+        line: usize,
+        column: usize,
+        character: char,
+    },
+    /// A row that does not have the expected number of cells.
+    WrongLength {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The input ended in the middle of a board, after `rows` rows.
+    Incomplete { rows: usize },
+    /// A number that is not a cell value from 0 to [`N`], where 0 is an empty
+    /// cell.
+    InvalidValue {
+        line: usize,
+        column: usize,
+        value: String,
+    },
+    /// A board that does not have the expected number of rows.
+    WrongRowCount { expected: usize, found: usize },
+    /// A region that does not consist of [`N`] distinct cells on the board.
+    InvalidRegion,
+    /// Packed bytes of a board that do not have the expected length.
+    WrongByteLength { expected: usize, found: usize },
+    /// Packed bytes of a board with an odd number of cells whose unused low
+    /// bits of the last byte are not zero.
+    NonZeroPadding { nibble: u8 },
+    /// Non-blank input after a single puzzle, which may be another puzzle.
+    /// The line number is 1-based.
+    ExtraInput { line: usize },
+    /// A board written cell by cell that does not have the expected number of
+    /// cells.
+    WrongCellCount { expected: usize, found: usize },
+    /// A cage of a killer Sudoku that is in the grid but has no sum.
+    CageWithoutSum { cage: char },
+    /// A cage of a killer Sudoku that has a sum but is not in the grid. The
+    /// line number is 1-based.
+    CageWithoutCells { line: usize, cage: char },
+    /// A cage of a killer Sudoku whose sum is given more than once. The line
+    /// number is 1-based.
+    DuplicateCage { line: usize, cage: char },
+    /// A cage of a killer Sudoku whose distinct values cannot add up to its
+    /// sum, because the sum is too small or too large for its number of
+    /// cells, or it has more cells than values. The line number is 1-based.
+    InvalidCageSum {
+        line: usize,
+        cage: char,
+        cells: usize,
+        sum: usize,
+    },
+}
+
+impl Display for SudokuError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SudokuError::InvalidCell {
+                line,
+                column,
+                character,
+            } => write!(
+                f,
+                "line {}, column {}: invalid cell {:?}",
+                line, column, character
+            ),
+            SudokuError::WrongLength {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {}: expected {} cells, found {}",
+                line, expected, found
+            ),
+            SudokuError::Incomplete { rows } => {
+                write!(f, "incomplete board: only {} rows", rows)
+            }
+            SudokuError::InvalidValue {
+                line,
+                column,
+                value,
+            } => write!(
+                f,
+                "line {}, column {}: invalid value {:?}",
+                line, column, value
+            ),
+            SudokuError::WrongRowCount { expected, found } => {
+                write!(f, "expected {} rows, found {}", expected, found)
+            }
+            SudokuError::InvalidRegion => {
+                write!(f, "a region must have {} distinct cells on the board", N)
+            }
+            SudokuError::WrongByteLength { expected, found } => {
+                write!(f, "expected {} bytes, found {}", expected, found)
+            }
+            SudokuError::NonZeroPadding { nibble } => {
+                write!(f, "padding of the last byte is {}, not 0", nibble)
+            }
+            SudokuError::ExtraInput { line } => {
+                write!(f, "line {}: unexpected input after the puzzle", line)
+            }
+            SudokuError::WrongCellCount { expected, found } => {
+                write!(f, "expected {} cells, found {}", expected, found)
+            }
+            SudokuError::CageWithoutSum { cage } => write!(f, "cage {:?} has no sum", cage),
+            SudokuError::CageWithoutCells { line, cage } => {
+                write!(f, "line {}: cage {:?} has no cells", line, cage)
+            }
+            SudokuError::DuplicateCage { line, cage } => {
+                write!(f, "line {}: cage {:?} has more than one sum", line, cage)
+            }
+            SudokuError::InvalidCageSum {
+                line,
+                cage,
+                cells,
+                sum,
+            } => write!(
+                f,
+                "line {}: cage {:?} of {} cells cannot add up to {}",
+                line, cage, cells, sum
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SudokuError {}
@@ -0,0 +1,1669 @@
+//! Reading and writing puzzles.
+//!
+//! The formats are described for a board of [`N`] by [`N`] cells, which is 9
+//! unless another size is configured. In the collection file formats, the
+//! digits from `1` to `N` are givens and any of [`EMPTY_CELLS`] is an empty
+//! cell. In the structured JSON and CSV formats, cells are numbers from 0 to
+//! `N`, where 0 is an empty cell.
+
+use crate::board::*;
+use crate::error::SudokuError;
+use std::convert::TryFrom;
+use std::io::BufRead;
+use std::io::Error;
+use std::io::ErrorKind;
+
+fn eof() -> Error {
+    Error::new(ErrorKind::UnexpectedEof, "malformed problem")
+}
+
+/// Characters that [`read_problem`] takes as empty cells.
+pub const EMPTY_CELLS: &[char] = &['0', '.', '_', '*'];
+
+/// Reads a puzzle from `input` to the end and parses it like
+/// [`Board::try_from`], so the grid, `.sdm`, CSV, and JSON encodings are all
+/// accepted by the same rules.
+///
+/// A parse error is reported as an [`ErrorKind::InvalidData`] error of the
+/// [`SudokuError`], except that an input that ends before the grid or CSV
+/// rows are complete is an [`ErrorKind::UnexpectedEof`] error. Anything but
+/// blank lines and comments after the puzzle is a
+/// [`SudokuError::ExtraInput`], as it may be another puzzle pasted by
+/// mistake. Use [`parse_sdm`] or [`parse_sdk`] to read many puzzles.
+pub fn read_problem<R: BufRead>(input: R) -> std::io::Result<Board<Option<usize>>> {
+    read_problem_with(input, EMPTY_CELLS)
+}
+
+/// Like [`read_problem`], but takes the characters in `empty` as empty cells.
+pub fn read_problem_with<R: BufRead>(
+    mut input: R,
+    empty: &[char],
+) -> std::io::Result<Board<Option<usize>>> {
+    let mut text = String::new();
+    input.read_to_string(&mut text)?;
+    let json = text.trim_start().starts_with('[');
+    parse_problem(&text, empty).map_err(|error| match error {
+        SudokuError::WrongRowCount { expected, found } if !json && found < expected => eof(),
+        error => invalid_data(error),
+    })
+}
+
+fn invalid_data(error: SudokuError) -> Error {
+    Error::new(ErrorKind::InvalidData, error)
+}
+
+/// Classifies a cell character: a value from 1 to `N`, or one of `empty`.
+fn classify_cell(c: char, empty: &[char]) -> Option<Option<usize>> {
+    if empty.contains(&c) {
+        return Some(None);
+    }
+    match c.to_digit(10) {
+        Some(n) if (1..=N as u32).contains(&n) => Some(Some(n as usize - 1)),
+        _ => None,
+    }
+}
+
+/// Parses `line` into `cells`, which must have exactly as many cells as the
+/// line, taking the characters in `empty` as empty cells. `number` is the
+/// 1-based line number for error reporting.
+fn parse_cells(
+    line: &str,
+    number: usize,
+    empty: &[char],
+    cells: &mut [Option<usize>],
+) -> Result<(), SudokuError> {
+    let found = line.chars().count();
+    if found != cells.len() {
+        return Err(SudokuError::WrongLength {
+            line: number,
+            expected: cells.len(),
+            found,
+        });
+    }
+    for (k, c) in line.chars().enumerate() {
+        cells[k] = classify_cell(c, empty).ok_or(SudokuError::InvalidCell {
+            line: number,
+            column: k + 1,
+            character: c,
+        })?;
+    }
+    Ok(())
+}
+
+/// Iterates lines that are neither blank nor comments, with 1-based line
+/// numbers and surrounding whitespace trimmed.
+fn content_lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(k, line)| (k + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Parses a puzzle line of the `.sdm` format.
+fn parse_sdm_line(
+    line: &str,
+    number: usize,
+    empty: &[char],
+) -> Result<Board<Option<usize>>, SudokuError> {
+    let mut cells = [None; N * N];
+    parse_cells(line, number, empty, &mut cells)?;
+    let mut board = Board([[None; N]; N]);
+    for (k, &cell) in cells.iter().enumerate() {
+        board.0[k / N][k % N] = cell;
+    }
+    Ok(board)
+}
+
+/// Parses the `.sdm` format, which has one puzzle per line of `N * N` cells.
+pub fn parse_sdm(input: &str) -> Result<Vec<Board<Option<usize>>>, SudokuError> {
+    content_lines(input)
+        .map(|(number, line)| parse_sdm_line(line, number, EMPTY_CELLS))
+        .collect()
+}
+
+/// Reads the `.sdm` format like [`parse_sdm`], but one line at a time, so
+/// that a huge input can be processed without holding it all in memory.
+///
+/// The iterator yields an error for each line that cannot be read or parsed,
+/// and goes on to the next line.
+pub fn read_sdm<R: BufRead>(
+    input: R,
+) -> impl Iterator<Item = std::io::Result<Board<Option<usize>>>> {
+    input
+        .lines()
+        .enumerate()
+        .filter_map(|(k, line)| match line {
+            Ok(line) => {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    None
+                } else {
+                    Some(parse_sdm_line(line, k + 1, EMPTY_CELLS).map_err(invalid_data))
+                }
+            }
+            Err(error) => Some(Err(error)),
+        })
+}
+
+/// Parses the `.sdk` format, which has a grid of `N` lines of `N` cells per
+/// puzzle. Metadata lines starting with `#` are skipped.
+pub fn parse_sdk(input: &str) -> Result<Vec<Board<Option<usize>>>, SudokuError> {
+    let mut boards = Vec::new();
+    let mut board = Board([[None; N]; N]);
+    let mut rows = 0;
+    for (number, line) in content_lines(input) {
+        parse_cells(line, number, EMPTY_CELLS, &mut board.0[rows])?;
+        rows += 1;
+        if rows == N {
+            boards.push(board);
+            rows = 0;
+        }
+    }
+    if rows != 0 {
+        return Err(SudokuError::Incomplete { rows });
+    }
+    Ok(boards)
+}
+
+/// Parses a Sukaku, which lists the candidates of each of the `N * N` cells
+/// in row-major order, separated by whitespace.
+///
+/// The candidates of a cell are written together, like `138`, or separated
+/// by commas on a board with values of more than one digit. Line breaks
+/// between the cells are optional, and the `|` and `-+-` rules written by
+/// [`format_candidates`] are skipped, so its output can be read back. Blank
+/// lines and comment lines starting with `#` are skipped, too.
+pub fn parse_sukaku(input: &str) -> Result<Board<SolvingCell>, SudokuError> {
+    let mut cells = Vec::with_capacity(N * N);
+    for (k, line) in input.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty()
+            || trimmed.starts_with('#')
+            || trimmed.chars().all(|c| c == '-' || c == '+')
+        {
+            continue;
+        }
+        let mut start = None;
+        for (offset, c) in line
+            .char_indices()
+            .chain(std::iter::once((line.len(), ' ')))
+        {
+            if !c.is_whitespace() {
+                start = start.or(Some(offset));
+            } else if let Some(start) = start.take() {
+                let token = &line[start..offset];
+                if token != "|" {
+                    let column = line[..start].chars().count() + 1;
+                    cells.push(parse_candidates(token, k + 1, column)?);
+                }
+            }
+        }
+    }
+    if cells.len() != N * N {
+        return Err(SudokuError::WrongCellCount {
+            expected: N * N,
+            found: cells.len(),
+        });
+    }
+    let mut board = Board([[SolvingCell::new(None); N]; N]);
+    for (k, cell) in cells.into_iter().enumerate() {
+        board.0[k / N][k % N] = cell;
+    }
+    Ok(board)
+}
+
+/// Parses the cages of a killer Sudoku, given as a grid of `N` lines of `N`
+/// letters and a line of `letter=sum` for each letter, like `A=12`.
+///
+/// The cells with the same letter form a cage, where upper and lower case
+/// letters are different cages. Whitespace between the letters of a row and
+/// around `=` is ignored, and blank lines and comment lines starting with `#`
+/// are skipped, so the sums can be written before or after the grid. Every
+/// cell has a letter, so it is in exactly one cage. The sum of each cage must
+/// be possible for its number of cells, from 1 + 2 = 3 to 8 + 9 = 17 for two
+/// cells on the 9x9 board, for example.
+///
+/// The cages are returned in the order of their first cells in row-major
+/// order, with their cells in row-major order.
+/// They can be solved by setting them as
+/// [`Options::cages`](crate::solver::Options::cages).
+pub fn parse_killer(input: &str) -> Result<Vec<Cage>, SudokuError> {
+    let mut grid = Vec::with_capacity(N);
+    let mut sums: Vec<(char, usize, usize)> = Vec::new();
+    for (number, line) in content_lines(input) {
+        if let Some((letter, sum)) = line.split_once('=') {
+            let cage = parse_cage_letter(letter.trim_end(), number)?;
+            let sum = sum.trim_start();
+            let column = line.chars().count() - sum.chars().count() + 1;
+            let value = sum.parse().map_err(|_| SudokuError::InvalidValue {
+                line: number,
+                column,
+                value: sum.to_string(),
+            })?;
+            if sums.iter().any(|&(c, _, _)| c == cage) {
+                return Err(SudokuError::DuplicateCage { line: number, cage });
+            }
+            sums.push((cage, value, number));
+        } else {
+            let mut row = Vec::with_capacity(N);
+            for (k, c) in line.chars().enumerate() {
+                if c.is_ascii_alphabetic() {
+                    row.push(c);
+                } else if !c.is_whitespace() {
+                    return Err(SudokuError::InvalidCell {
+                        line: number,
+                        column: k + 1,
+                        character: c,
+                    });
+                }
+            }
+            if row.len() != N {
+                return Err(SudokuError::WrongLength {
+                    line: number,
+                    expected: N,
+                    found: row.len(),
+                });
+            }
+            grid.push(row);
+        }
+    }
+    if grid.len() != N {
+        return Err(SudokuError::WrongRowCount {
+            expected: N,
+            found: grid.len(),
+        });
+    }
+
+    let mut cages: Vec<(char, Cage)> = Vec::new();
+    for (i, row) in grid.iter().enumerate() {
+        for (j, &cage) in row.iter().enumerate() {
+            match cages.iter_mut().find(|(c, _)| *c == cage) {
+                Some((_, found)) => found.cells.push((i, j)),
+                None => {
+                    let sum = sums
+                        .iter()
+                        .find(|&&(c, _, _)| c == cage)
+                        .ok_or(SudokuError::CageWithoutSum { cage })?
+                        .1;
+                    let cells = vec![(i, j)];
+                    cages.push((cage, Cage { cells, sum }));
+                }
+            }
+        }
+    }
+    for &(cage, sum, line) in &sums {
+        let cells = match cages.iter().find(|(c, _)| *c == cage) {
+            Some((_, found)) => found.cells.len(),
+            None => return Err(SudokuError::CageWithoutCells { line, cage }),
+        };
+        // The least and greatest sums of `cells` distinct values from 1 to N.
+        let least = cells * (cells + 1) / 2;
+        let greatest = cells * (2 * N + 1).saturating_sub(cells) / 2;
+        if cells > N || !(least..=greatest).contains(&sum) {
+            return Err(SudokuError::InvalidCageSum {
+                line,
+                cage,
+                cells,
+                sum,
+            });
+        }
+    }
+    Ok(cages.into_iter().map(|(_, cage)| cage).collect())
+}
+
+/// Parses the letter of a cage before `=` at the given 1-based line.
+fn parse_cage_letter(letter: &str, line: usize) -> Result<char, SudokuError> {
+    for (k, c) in letter.chars().enumerate() {
+        if k > 0 || !c.is_ascii_alphabetic() {
+            return Err(SudokuError::InvalidCell {
+                line,
+                column: k + 1,
+                character: c,
+            });
+        }
+    }
+    letter.chars().next().ok_or(SudokuError::InvalidCell {
+        line,
+        column: 1,
+        character: '=',
+    })
+}
+
+/// Parses the candidates of a cell of a Sukaku at the given 1-based line and
+/// column.
+fn parse_candidates(token: &str, line: usize, column: usize) -> Result<SolvingCell, SudokuError> {
+    let mut candidates = Vec::new();
+    if N < 10 {
+        for (k, c) in token.chars().enumerate() {
+            match c.to_digit(10) {
+                Some(n) if (1..=N as u32).contains(&n) => candidates.push(n as usize - 1),
+                _ => {
+                    return Err(SudokuError::InvalidCell {
+                        line,
+                        column: column + k,
+                        character: c,
+                    })
+                }
+            }
+        }
+    } else {
+        let mut column = column;
+        for value in token.split(',') {
+            match parse_value(value, line, column)? {
+                Some(n) => candidates.push(n),
+                None => {
+                    return Err(SudokuError::InvalidValue {
+                        line,
+                        column,
+                        value: value.to_string(),
+                    })
+                }
+            }
+            column += value.chars().count() + 1;
+        }
+    }
+    Ok(SolvingCell::from_candidates(candidates))
+}
+
+/// Parses a cell value in the structured formats, where `0` is an empty cell.
+fn parse_value(value: &str, line: usize, column: usize) -> Result<Option<usize>, SudokuError> {
+    match value.parse::<usize>() {
+        Ok(0) => Ok(None),
+        Ok(n) if n <= N => Ok(Some(n - 1)),
+        _ => Err(SudokuError::InvalidValue {
+            line,
+            column,
+            value: value.to_string(),
+        }),
+    }
+}
+
+/// Parses a puzzle of `N` lines of `N` comma-separated values from 0 to `N`,
+/// where 0 or an empty field is an empty cell.
+pub fn parse_csv(input: &str) -> Result<Board<Option<usize>>, SudokuError> {
+    let mut board = Board::empty();
+    let mut rows = 0;
+    for (number, line) in content_lines(input) {
+        let fields: Vec<&str> = line.split(',').collect();
+        if fields.len() != N {
+            return Err(SudokuError::WrongLength {
+                line: number,
+                expected: N,
+                found: fields.len(),
+            });
+        }
+        if rows < N {
+            let mut column = 1;
+            for (j, field) in fields.iter().enumerate() {
+                let value = field.trim();
+                if !value.is_empty() {
+                    board.0[rows][j] = parse_value(value, number, column)?;
+                }
+                column += field.chars().count() + 1;
+            }
+        }
+        rows += 1;
+    }
+    if rows != N {
+        return Err(SudokuError::WrongRowCount {
+            expected: N,
+            found: rows,
+        });
+    }
+    Ok(board)
+}
+
+/// Iterates the tokens of a JSON text with their 1-based line and column
+/// numbers.
+///
+/// A token is either a run of alphanumerics, `-`, and `.`, which covers any
+/// JSON number or literal, or a single other non-whitespace character.
+struct JsonTokens<'a> {
+    input: &'a str,
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    line: usize,
+    column: usize,
+}
+
+fn is_json_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '.'
+}
+
+impl<'a> JsonTokens<'a> {
+    fn new(input: &'a str) -> Self {
+        JsonTokens {
+            input,
+            chars: input.char_indices().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn advance(&mut self) -> Option<(usize, char)> {
+        let (k, c) = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some((k, c))
+    }
+}
+
+impl<'a> Iterator for JsonTokens<'a> {
+    type Item = (&'a str, usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.chars.peek()?.1.is_whitespace() {
+            self.advance();
+        }
+        let (line, column) = (self.line, self.column);
+        let (start, c) = self.advance()?;
+        let mut end = start + c.len_utf8();
+        if is_json_word(c) {
+            while let Some(&(k, c)) = self.chars.peek() {
+                if !is_json_word(c) {
+                    break;
+                }
+                self.advance();
+                end = k + c.len_utf8();
+            }
+        }
+        Some((&self.input[start..end], line, column))
+    }
+}
+
+/// Parses a puzzle given as a JSON array of `N` rows, each an array of `N`
+/// numbers from 0 to `N`, where 0 is an empty cell.
+pub fn parse_json(input: &str) -> Result<Board<Option<usize>>, SudokuError> {
+    let mut tokens = JsonTokens::new(input);
+    let mut rows: Vec<Vec<Option<usize>>> = Vec::new();
+    let unexpected = |(token, line, column): (&str, usize, usize)| SudokuError::InvalidCell {
+        line,
+        column,
+        character: token.chars().next().unwrap(),
+    };
+    macro_rules! next {
+        () => {
+            tokens
+                .next()
+                .ok_or(SudokuError::Incomplete { rows: rows.len() })?
+        };
+    }
+
+    let token = next!();
+    if token.0 != "[" {
+        return Err(unexpected(token));
+    }
+    loop {
+        let (token, line, column) = next!();
+        if token != "[" {
+            return Err(unexpected((token, line, column)));
+        }
+        let mut row = Vec::new();
+        loop {
+            let (value, line, column) = next!();
+            if !value.starts_with(is_json_word) {
+                return Err(unexpected((value, line, column)));
+            }
+            row.push(parse_value(value, line, column)?);
+            match next!() {
+                (",", _, _) => continue,
+                ("]", _, _) => break,
+                token => return Err(unexpected(token)),
+            }
+        }
+        if row.len() != N {
+            return Err(SudokuError::WrongLength {
+                line,
+                expected: N,
+                found: row.len(),
+            });
+        }
+        rows.push(row);
+        match next!() {
+            (",", _, _) => continue,
+            ("]", _, _) => break,
+            token => return Err(unexpected(token)),
+        }
+    }
+    if let Some(token) = tokens.next() {
+        return Err(unexpected(token));
+    }
+    if rows.len() != N {
+        return Err(SudokuError::WrongRowCount {
+            expected: N,
+            found: rows.len(),
+        });
+    }
+
+    let mut board = Board::empty();
+    for (i, row) in rows.iter().enumerate() {
+        board.0[i].copy_from_slice(row);
+    }
+    Ok(board)
+}
+
+/// Parses a puzzle in whichever of the common encodings `input` is in.
+///
+/// The encoding is detected by these rules, in order:
+///
+/// 1. If the first non-whitespace character is `[`, the input is JSON, read
+///    by [`parse_json`].
+/// 2. If the first line that is neither blank nor a comment contains a
+///    comma, the input is CSV, read by [`parse_csv`].
+/// 3. If there is only one line that is neither blank nor a comment and it
+///    has more than `N` characters other than whitespace, it is a line of
+///    `N * N` cells as in the `.sdm` format, read by [`parse_sdm`].
+/// 4. Otherwise, the input is a grid of `N` lines of `N` cells, where `1`
+///    to `N` are givens and any of [`EMPTY_CELLS`] is an empty cell. Both
+///    contiguous cells and cells separated by any whitespace are accepted,
+///    such as the space-separated form the solver prints for a solution or
+///    the tab-separated rows exported by a spreadsheet. A line of exactly
+///    `N` characters is read as `N` cells, including spaces, which are then
+///    empty cells. Any other character is an error.
+///
+/// Blank lines and lines starting with `#` are skipped in the latter three,
+/// except that a line of `N` spaces is a row of the grid. Any line left
+/// after the `N` rows is a [`SudokuError::ExtraInput`]. The error tells why
+/// the input does not parse in the detected encoding.
+impl TryFrom<&str> for Board<Option<usize>> {
+    type Error = SudokuError;
+
+    fn try_from(input: &str) -> Result<Self, SudokuError> {
+        parse_problem(input, EMPTY_CELLS)
+    }
+}
+
+/// Parses `input` as [`Board::try_from`] does, taking the characters in
+/// `empty` as empty cells.
+fn parse_problem(input: &str, empty: &[char]) -> Result<Board<Option<usize>>, SudokuError> {
+    if input.trim_start().starts_with('[') {
+        return parse_json(input);
+    }
+    let csv = content_lines(input)
+        .next()
+        .is_some_and(|(_, line)| line.contains(','));
+    let rows: Vec<(usize, &str)> = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| is_row(line, !csv))
+        .collect();
+    if let Some(&(k, _)) = rows.get(N) {
+        return Err(SudokuError::ExtraInput { line: k + 1 });
+    }
+    // The rest after the last row is blank lines and comments.
+    let end = rows.last().map_or(0, |&(k, _)| k + 1);
+    let lines = input.lines().take(end);
+    match rows[..] {
+        _ if csv => parse_csv(&lines.collect::<Vec<_>>().join("\n")),
+        [(k, line)] if line.chars().filter(|c| !c.is_whitespace()).count() > N => {
+            parse_sdm_line(line.trim(), k + 1, empty)
+        }
+        _ => parse_grid(lines, empty),
+    }
+}
+
+/// Whether `line` is a row of a puzzle, that is, neither blank nor a comment.
+/// If `fixed_width`, a line of `N` spaces is a row of `N` empty cells.
+fn is_row(line: &str, fixed_width: bool) -> bool {
+    let trimmed = line.trim();
+    (!trimmed.is_empty() || (fixed_width && line.chars().count() == N)) && !trimmed.starts_with('#')
+}
+
+/// Parses the grid encoding of [`Board::try_from`] from `lines`, which are
+/// numbered from 1.
+fn parse_grid<'a, I>(lines: I, empty: &[char]) -> Result<Board<Option<usize>>, SudokuError>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let mut board = Board::empty();
+    let mut rows = 0;
+    for (k, line) in lines.enumerate().filter(|(_, line)| is_row(line, true)) {
+        let fixed_width = line.chars().count() == N;
+        let mut cells = [None; N];
+        let mut found = 0;
+        for (column, c) in line.chars().enumerate() {
+            if c.is_whitespace() && !(fixed_width && c == ' ') {
+                continue;
+            }
+            let cell = if c == ' ' {
+                None
+            } else {
+                classify_cell(c, empty).ok_or(SudokuError::InvalidCell {
+                    line: k + 1,
+                    column: column + 1,
+                    character: c,
+                })?
+            };
+            if found < N {
+                cells[found] = cell;
+            }
+            found += 1;
+        }
+        if found != N {
+            return Err(SudokuError::WrongLength {
+                line: k + 1,
+                expected: N,
+                found,
+            });
+        }
+        board.0[rows] = cells;
+        rows += 1;
+    }
+    if rows != N {
+        return Err(SudokuError::WrongRowCount {
+            expected: N,
+            found: rows,
+        });
+    }
+    Ok(board)
+}
+
+/// Renders `solution` as a JSON array of rows of numbers from 1 to `N`.
+pub fn to_json(solution: &Board<usize>) -> String {
+    let rows: Vec<String> = solution
+        .0
+        .iter()
+        .map(|row| {
+            let cells: Vec<String> = row.iter().map(|n| (n + 1).to_string()).collect();
+            format!("[{}]", cells.join(","))
+        })
+        .collect();
+    format!("[{}]", rows.join(","))
+}
+
+/// Renders the candidates of `board` as a JSON array of rows, where each
+/// cell is an array of its candidates from 1 to `N`, for drawing pencil marks.
+pub fn candidates_to_json(board: &Board<SolvingCell>) -> String {
+    let rows: Vec<String> = board
+        .0
+        .iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|cell| {
+                    let values: Vec<String> = cell.iter().map(|n| (n + 1).to_string()).collect();
+                    format!("[{}]", values.join(","))
+                })
+                .collect();
+            format!("[{}]", cells.join(","))
+        })
+        .collect();
+    format!("[{}]", rows.join(","))
+}
+
+/// How [`format_filled`] marks the cells filled in by the solver.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Marking {
+    /// Filled cells are enclosed in parentheses, and givens are padded with
+    /// spaces to the same width.
+    #[default]
+    Parentheses,
+    /// Filled cells are colored with ANSI escape sequences, for terminals.
+    Color,
+}
+
+/// Formats `solution` like its [`Display`](std::fmt::Display), but with the
+/// cells that are empty in `problem` marked as specified by `marking`.
+pub fn format_filled(
+    problem: &Board<Option<usize>>,
+    solution: &Board<usize>,
+    marking: Marking,
+) -> String {
+    format_with_givens(problem, solution, |given, n| match (given, marking) {
+        (true, Marking::Parentheses) => format!(" {} ", n + 1),
+        (false, Marking::Parentheses) => format!("({})", n + 1),
+        (true, Marking::Color) => format!("{}", n + 1),
+        (false, Marking::Color) => format!("\x1b[36m{}\x1b[0m", n + 1),
+    })
+}
+
+/// How [`format_answer_key`] emphasizes the givens.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Emphasis {
+    /// Givens are enclosed in brackets, and filled cells are padded with
+    /// spaces to the same width, which survives plain-text printing.
+    #[default]
+    Brackets,
+    /// Givens are made bold with ANSI escape sequences, for terminals.
+    Bold,
+}
+
+/// Formats `solution` as the answer key of `problem`, with the givens of
+/// `problem` emphasized as specified by `emphasis`, so that a reader can find
+/// the puzzle in the answer.
+///
+/// This is the converse of [`format_filled`], which marks the other cells.
+pub fn format_answer_key(
+    problem: &Board<Option<usize>>,
+    solution: &Board<usize>,
+    emphasis: Emphasis,
+) -> String {
+    format_with_givens(problem, solution, |given, n| match (given, emphasis) {
+        (true, Emphasis::Brackets) => format!("[{}]", n + 1),
+        (false, Emphasis::Brackets) => format!(" {} ", n + 1),
+        (true, Emphasis::Bold) => format!("\x1b[1m{}\x1b[0m", n + 1),
+        (false, Emphasis::Bold) => format!("{}", n + 1),
+    })
+}
+
+/// Formats `solution` in rows of cells separated by spaces, where `cell`
+/// formats a value given whether the cell is a given of `problem`.
+fn format_with_givens<F>(problem: &Board<Option<usize>>, solution: &Board<usize>, cell: F) -> String
+where
+    F: Fn(bool, usize) -> String,
+{
+    let mut result = String::new();
+    for (given_row, row) in problem.into_iter().zip(solution) {
+        let cells: Vec<String> = given_row
+            .iter()
+            .zip(row)
+            .map(|(given, &n)| cell(given.is_some(), n))
+            .collect();
+        result.push_str(&cells.join(" "));
+        result.push('\n');
+    }
+    result
+}
+
+/// Formats the candidates of each cell of `board` in a grid, with the stacks
+/// of blocks separated by `|` and the bands by a line of `-`.
+///
+/// A cell lists its candidates in ascending order, without separators if all
+/// values have one digit and separated by commas otherwise. Every cell is
+/// padded to the width of the widest one, so the columns align however many
+/// candidates the cells have.
+pub fn format_candidates(board: &Board<SolvingCell>) -> String {
+    let separator = if N < 10 { "" } else { "," };
+    let cells = board.map(|cell| {
+        let values: Vec<String> = cell.iter().map(|n| (n + 1).to_string()).collect();
+        values.join(separator)
+    });
+    let width = cells.0.iter().flatten().map(String::len).max().unwrap_or(0);
+
+    let stack_rule = "-".repeat(BLOCK_COLS * (width + 1) - 1);
+    let band_rule = vec![stack_rule; N / BLOCK_COLS].join("-+-");
+    let mut result = String::new();
+    for (i, row) in cells.into_iter().enumerate() {
+        if i > 0 && i % BLOCK_ROWS == 0 {
+            result.push_str(&band_rule);
+            result.push('\n');
+        }
+        let mut line = String::new();
+        for (j, cell) in row.iter().enumerate() {
+            if j > 0 {
+                line.push_str(if j % BLOCK_COLS == 0 { " | " } else { " " });
+            }
+            line.push_str(&format!("{:<1$}", cell, width));
+        }
+        result.push_str(line.trim_end());
+        result.push('\n');
+    }
+    result
+}
+
+/// Writes `solution` to `w` as the solver prints it, that is, like its
+/// [`Display`](std::fmt::Display) followed by an empty line that separates
+/// it from the next solution.
+pub fn write_solution<W: std::fmt::Write>(w: &mut W, solution: &Board<usize>) -> std::fmt::Result {
+    writeln!(w, "{}", solution)
+}
+
+/// Length of a board packed by `to_bytes`, with four bits per cell.
+pub const PACKED_LEN: usize = (N * N).div_ceil(2);
+
+/// Packs the four-bit `nibble` of each cell in row-major order, the first of each pair in the
+/// high bits.
+fn pack<F: Fn(usize, usize) -> u8>(nibble: F) -> [u8; PACKED_LEN] {
+    let mut bytes = [0; PACKED_LEN];
+    for k in 0..N * N {
+        let shift = if k % 2 == 0 { 4 } else { 0 };
+        bytes[k / 2] |= nibble(k / N, k % N) << shift;
+    }
+    bytes
+}
+
+/// Unpacks `bytes` packed by [`pack`], converting each nibble with `cell`,
+/// which returns `None` for an invalid nibble. The padding of the last byte
+/// must be zero, so that each board has only one packed form.
+fn unpack<T, F>(bytes: &[u8], cell: F) -> Result<Board<T>, SudokuError>
+where
+    T: Copy + Default,
+    F: Fn(u8) -> Option<T>,
+{
+    if bytes.len() != PACKED_LEN {
+        return Err(SudokuError::WrongByteLength {
+            expected: PACKED_LEN,
+            found: bytes.len(),
+        });
+    }
+    let padding = if N * N % 2 == 1 {
+        bytes[PACKED_LEN - 1] & 0xF
+    } else {
+        0
+    };
+    if padding != 0 {
+        return Err(SudokuError::NonZeroPadding { nibble: padding });
+    }
+    let mut board = Board([[T::default(); N]; N]);
+    for k in 0..N * N {
+        let shift = if k % 2 == 0 { 4 } else { 0 };
+        let nibble = (bytes[k / 2] >> shift) & 0xF;
+        let (i, j) = (k / N, k % N);
+        board.0[i][j] = cell(nibble).ok_or_else(|| SudokuError::InvalidValue {
+            line: i + 1,
+            column: j + 1,
+            value: nibble.to_string(),
+        })?;
+    }
+    Ok(board)
+}
+
+impl Board<Option<usize>> {
+    /// Packs `self` in [`PACKED_LEN`] bytes, four bits per cell in row-major
+    /// order with 0 for an empty cell and 1 to `N` for a clue.
+    ///
+    /// Two cells share a byte, the first in the high bits. The unused low
+    /// bits of the last byte are zero.
+    ///
+    /// # Panics
+    ///
+    /// This function panics on a board larger than 15x15, where a clue may
+    /// not fit in four bits.
+    #[allow(clippy::assertions_on_constants)]
+    pub fn to_bytes(&self) -> [u8; PACKED_LEN] {
+        assert!(
+            N < 16,
+            "a clue of a {0}x{0} board does not fit in four bits",
+            N
+        );
+        pack(|i, j| self.0[i][j].map_or(0, |n| n as u8 + 1))
+    }
+
+    /// Unpacks a puzzle packed by [`to_bytes`](Self::to_bytes).
+    ///
+    /// In an error, the line and column are the 1-based row and column of the
+    /// invalid cell. The unused low bits of the last byte must be zero.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Board<Option<usize>>, SudokuError> {
+        unpack(bytes, |nibble| match nibble as usize {
+            0 => Some(None),
+            n if n <= N => Some(Some(n - 1)),
+            _ => None,
+        })
+    }
+}
+
+impl Board<usize> {
+    /// Packs `self` in [`PACKED_LEN`] bytes, four bits per cell in row-major
+    /// order with the 0-based values.
+    ///
+    /// The layout is the same as that of a packed puzzle.
+    ///
+    /// # Panics
+    ///
+    /// This function panics on a board larger than 16x16, where a value may
+    /// not fit in four bits.
+    #[allow(clippy::assertions_on_constants)]
+    pub fn to_bytes(&self) -> [u8; PACKED_LEN] {
+        assert!(
+            N <= 16,
+            "a value of a {0}x{0} board does not fit in four bits",
+            N
+        );
+        pack(|i, j| self.0[i][j] as u8)
+    }
+
+    /// Unpacks a solution packed by [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Board<usize>, SudokuError> {
+        unpack(bytes, |nibble| Some(nibble as usize).filter(|&n| n < N))
+    }
+}
+
+// The tests are written for the 9x9 board.
+#[cfg(all(test, not(any(board_4x4, board_6x6, board_16x16, board_25x25))))]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn read_problem_contiguous() {
+        let input = "\
+100400709
+050780020
+709023006
+300600000
+640070012
+908002045
+230504800
+060090030
+807001064
+";
+        let board = read_problem(input.as_bytes()).unwrap();
+        assert_eq!(board.0[0][0], Some(0));
+        assert_eq!(board.0[0][1], None);
+        assert_eq!(board.0[8][8], Some(3));
+    }
+
+    #[test]
+    fn read_problem_round_trip() {
+        let solution = Board([
+            [0, 1, 2, 3, 4, 5, 6, 7, 8],
+            [3, 4, 5, 6, 7, 8, 0, 1, 2],
+            [6, 7, 8, 0, 1, 2, 3, 4, 5],
+            [2, 0, 1, 5, 3, 4, 8, 6, 7],
+            [5, 3, 4, 8, 6, 7, 2, 0, 1],
+            [8, 6, 7, 2, 0, 1, 5, 3, 4],
+            [1, 2, 0, 4, 5, 3, 7, 8, 6],
+            [4, 5, 3, 7, 8, 6, 1, 2, 0],
+            [7, 8, 6, 1, 2, 0, 4, 5, 3],
+        ]);
+        let mut expected = solution.map(|&n| Some(n));
+        // The solver prints an empty line after each solution.
+        let printed = format!("{}\n", solution);
+        assert_eq!(read_problem(printed.as_bytes()).unwrap(), expected);
+
+        // Empty cells in the same space-separated form.
+        let printed = printed.replacen("1 2 3", "0 2 0", 1);
+        expected.0[0][0] = None;
+        expected.0[0][2] = None;
+        assert_eq!(read_problem(printed.as_bytes()).unwrap(), expected);
+
+        // A puzzle is printed in that form.
+        assert_eq!(expected.to_string(), printed.trim_end().to_string() + "\n");
+        assert_eq!(
+            read_problem(expected.to_string().as_bytes()).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn read_problem_placeholders() {
+        let expected = Board::empty().with_clue(0, 0, 1).with_clue(8, 8, 9);
+        for &empty in EMPTY_CELLS {
+            let e = empty.to_string();
+            let mut input = format!("1{}\n", e.repeat(8));
+            input.push_str(&format!("{}\n", e.repeat(9)).repeat(7));
+            input.push_str(&format!("{}9\n", e.repeat(8)));
+            assert_eq!(
+                read_problem(input.as_bytes()).unwrap(),
+                expected,
+                "{:?}",
+                empty
+            );
+        }
+
+        // Spaces in fixed-width lines, and mixed placeholders.
+        let input = "1        \n.0_* .0_*\n".to_string() + &".........\n".repeat(6) + "        9\n";
+        assert_eq!(read_problem(input.as_bytes()).unwrap(), expected);
+    }
+
+    #[test]
+    fn read_problem_custom_placeholders() {
+        let input = "-".repeat(9) + "\n";
+        let input = input.repeat(8) + "--------9\n";
+        let board = read_problem_with(input.as_bytes(), &['-']).unwrap();
+        assert_eq!(board, Board::empty().with_clue(8, 8, 9));
+        assert!(read_problem(input.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn read_problem_eof() {
+        let error = read_problem("1 2 3 4 5 6 7 8 9\n".as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn read_problem_whitespace_separated() {
+        let expected = Board::empty().with_clue(0, 0, 1).with_clue(8, 8, 9);
+        let row = |cells: [char; N], separator: &str| {
+            let cells: Vec<String> = cells.iter().map(char::to_string).collect();
+            cells.join(separator)
+        };
+        let mut first = ['0'; N];
+        first[0] = '1';
+        let mut last = ['0'; N];
+        last[N - 1] = '9';
+        for separator in ["\t", "   ", " \t "] {
+            let mut input = row(first, separator) + separator + "\n";
+            for _ in 1..N - 1 {
+                input += &format!("{}{}\n", separator, row(['.'; N], separator));
+            }
+            input += &row(last, separator);
+            assert_eq!(
+                read_problem(input.as_bytes()).unwrap(),
+                expected,
+                "{:?}",
+                separator
+            );
+        }
+
+        // Line 1 is blank, so row `i` is on line `i + 2`.
+        let mut rows = vec![row(['0'; N], "\t"); N];
+        rows[1] = row(['0'; N], "\t")[2..].to_string();
+        rows[4].push_str("\t\t0");
+        let input = format!("\n{}\n", rows.join("\n"));
+        let error = read_problem(input.as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "line 3: expected 9 cells, found 8");
+        rows[1] = row(['0'; N], "\t");
+        let input = format!("\n{}\n", rows.join("\n"));
+        let error = read_problem(input.as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "line 6: expected 9 cells, found 10");
+
+        let error = read_problem("1 2 3 4 5 6 7 8\n".as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn read_problem_extra_rows() {
+        let puzzle = "\n".to_string() + &".........\n".repeat(8) + "........9\n";
+        let board = read_problem((puzzle.clone() + "\n  \n").as_bytes()).unwrap();
+        assert_eq!(board, Board::empty().with_clue(8, 8, 9));
+
+        let error = read_problem(puzzle.repeat(2).as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "line 12: unexpected input after the puzzle"
+        );
+        let error = read_problem((puzzle + ".........\n").as_bytes()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 11: unexpected input after the puzzle"
+        );
+    }
+
+    #[test]
+    fn read_problem_json() {
+        let mut rows = ["[0,0,0,0,0,0,0,0,0]"; N];
+        rows[0] = "[1, 0, 0, 0, 0, 0, 0, 0, 0]";
+        rows[8] = "[0,0,0,0,0,0,0,0,9]";
+        let input = format!("\n  [{}\n]\n", rows.join(",\n   "));
+        let board = read_problem(input.as_bytes()).unwrap();
+        assert_eq!(board, Board::empty().with_clue(0, 0, 1).with_clue(8, 8, 9));
+
+        let solution = Board([[4; N]; N]);
+        let board = read_problem(to_json(&solution).as_bytes()).unwrap();
+        assert_eq!(board, solution.map(|&n| Some(n)));
+    }
+
+    #[test]
+    fn read_problem_csv() {
+        let input = "1,0,0,0,0,0,0,0,0\n".to_string()
+            + &" , , , , , , , , \n".repeat(7)
+            + "0,0,0,0,0,0,0,0,9\n\n";
+        let board = read_problem(input.as_bytes()).unwrap();
+        assert_eq!(board, Board::empty().with_clue(0, 0, 1).with_clue(8, 8, 9));
+
+        let error = read_problem((input + "extra\n").as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "line 11: unexpected input after the puzzle"
+        );
+
+        let error = read_problem("1,0,0,0,0,0,0,0,0\n".as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+        let input = "1,2,3\n".repeat(9);
+        let error = read_problem(input.as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn try_from_detects_encoding() {
+        let expected = Board::empty().with_clue(0, 0, 1).with_clue(8, 8, 9);
+        let line = format!("1{}9", ".".repeat(N * N - 2));
+        let grid = format!(
+            "# comment\n1 0 0 0 0 0 0 0 0\n{}0 0 0 0 0 0 0 0 9\n",
+            "0 0 0 0 0 0 0 0 0\n".repeat(7)
+        );
+        let fixed_width = format!("1        \n{}        9\n", "         \n".repeat(7));
+        let csv = format!("1,,,,,,,,\n{},,,,,,,,9\n", ",,,,,,,,\n".repeat(7));
+        let json = to_json(&Board([[0; N]; N])).replace('1', "0");
+        for input in [line.as_str(), &grid, &fixed_width, &csv] {
+            assert_eq!(Board::try_from(input), Ok(expected), "{}", input);
+        }
+        assert_eq!(Board::try_from(json.as_str()), Ok(Board::empty()));
+
+        // A line too short for the `.sdm` format is a row of the grid.
+        assert_eq!(
+            Board::try_from("123"),
+            Err(SudokuError::WrongLength {
+                line: 1,
+                expected: N,
+                found: 3
+            })
+        );
+        assert_eq!(
+            Board::try_from("123456789\n123456789\n"),
+            Err(SudokuError::WrongRowCount {
+                expected: N,
+                found: 2
+            })
+        );
+        assert_eq!(
+            Board::try_from("123456789\n12345678x\n"),
+            Err(SudokuError::InvalidCell {
+                line: 2,
+                column: 9,
+                character: 'x'
+            })
+        );
+        assert_eq!(
+            Board::try_from("1 2 3\n1 2 3\n"),
+            Err(SudokuError::WrongLength {
+                line: 1,
+                expected: N,
+                found: 3
+            })
+        );
+    }
+
+    #[test]
+    fn read_problem_agrees_with_try_from() {
+        let line = format!("1{}9", "_".repeat(N * N - 2));
+        let grid = format!("1*******\t*\n{}********9\n", "*********\n".repeat(7));
+        let expected = Board::empty().with_clue(0, 0, 1).with_clue(8, 8, 9);
+        for input in [&line, &grid] {
+            assert_eq!(Board::try_from(input.as_str()), Ok(expected), "{}", input);
+            assert_eq!(
+                read_problem(input.as_bytes()).unwrap(),
+                expected,
+                "{}",
+                input
+            );
+        }
+        assert_eq!(parse_sdm(&line), Ok(vec![expected]));
+
+        let invalid = "12345678x9\n".to_string() + &".........\n".repeat(8);
+        let error = SudokuError::InvalidCell {
+            line: 1,
+            column: 9,
+            character: 'x',
+        };
+        assert_eq!(Board::try_from(invalid.as_str()), Err(error.clone()));
+        let read_error = read_problem(invalid.as_bytes()).unwrap_err();
+        assert_eq!(read_error.kind(), ErrorKind::InvalidData);
+        assert_eq!(read_error.to_string(), error.to_string());
+    }
+
+    #[test]
+    fn json_errors() {
+        let row = "[0,0,0,0,0,0,0,0,0]";
+        assert_eq!(
+            parse_json(&format!("[{}]", row)).unwrap_err(),
+            SudokuError::WrongRowCount {
+                expected: 9,
+                found: 1
+            }
+        );
+        assert_eq!(
+            parse_json(&format!("[{},\n[0,0]", row)).unwrap_err(),
+            SudokuError::WrongLength {
+                line: 2,
+                expected: 9,
+                found: 2
+            }
+        );
+        assert_eq!(
+            parse_json("[[0,10,0").unwrap_err(),
+            SudokuError::InvalidValue {
+                line: 1,
+                column: 5,
+                value: "10".to_string()
+            }
+        );
+        assert_eq!(
+            parse_json("[[0;").unwrap_err(),
+            SudokuError::InvalidCell {
+                line: 1,
+                column: 4,
+                character: ';'
+            }
+        );
+        assert_eq!(
+            parse_json(&format!("[{}", row)).unwrap_err(),
+            SudokuError::Incomplete { rows: 1 }
+        );
+        let input = format!("[{}]", [row; 9].join(","));
+        assert!(parse_json(&input).is_ok());
+        assert!(parse_json(&(input + "]")).is_err());
+    }
+
+    #[test]
+    fn csv_errors() {
+        let row = "0,0,0,0,0,0,0,0,0\n";
+        assert_eq!(
+            parse_csv(&row.repeat(10)).unwrap_err(),
+            SudokuError::WrongRowCount {
+                expected: 9,
+                found: 10
+            }
+        );
+        assert_eq!(
+            parse_csv(&(row.to_string() + "0,0,-1,0,0,0,0,0,0\n")).unwrap_err(),
+            SudokuError::InvalidValue {
+                line: 2,
+                column: 5,
+                value: "-1".to_string()
+            }
+        );
+        assert_eq!(
+            parse_csv("0,0\n").unwrap_err(),
+            SudokuError::WrongLength {
+                line: 1,
+                expected: 9,
+                found: 2
+            }
+        );
+    }
+
+    #[test]
+    fn filled_cells_marked() {
+        let solution = Board([[4; N]; N]);
+        let problem = Board::empty().with_clue(0, 0, 5);
+        let text = format_filled(&problem, &solution, Marking::Parentheses);
+        assert!(text.starts_with(" 5  (5) (5)"));
+        assert_eq!(text.lines().count(), N);
+        assert!(text.lines().all(|line| line.len() == 4 * N - 1));
+
+        let text = format_filled(&problem, &solution, Marking::Color);
+        assert!(text.starts_with("5 \x1b[36m5\x1b[0m "));
+
+        let full = solution.map(|&n| Some(n));
+        let text = format_filled(&full, &solution, Marking::Color);
+        assert_eq!(text, solution.to_string());
+    }
+
+    #[test]
+    fn answer_key_emphasizes_givens() {
+        let solution = Board([[4; N]; N]);
+        let problem = Board::empty().with_clue(0, 1, 5);
+        let text = format_answer_key(&problem, &solution, Emphasis::Brackets);
+        assert!(text.starts_with(" 5  [5]  5 "));
+        assert_eq!(text.lines().count(), N);
+        assert!(text.lines().all(|line| line.len() == 4 * N - 1));
+
+        let text = format_answer_key(&problem, &solution, Emphasis::Bold);
+        assert!(text.starts_with("5 \x1b[1m5\x1b[0m 5"));
+
+        let text = format_answer_key(&Board::empty(), &solution, Emphasis::Bold);
+        assert_eq!(text, solution.to_string());
+    }
+
+    #[test]
+    fn solution_to_buffer() {
+        let mut solution = Board([[0; N]; N]);
+        solution.0[8][8] = 8;
+        let mut buffer = String::new();
+        write_solution(&mut buffer, &solution).unwrap();
+        write_solution(&mut buffer, &solution).unwrap();
+        let expected = format!("{}\n", solution);
+        assert!(expected.ends_with("1 1 1 9\n\n"));
+        assert_eq!(buffer, expected.repeat(2));
+    }
+
+    #[test]
+    fn candidates_align() {
+        let problem = Board::empty().with_clue(0, 0, 5).with_clue(4, 4, 1);
+        let text = format_candidates(&crate::solver::propagate(&problem));
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), N + N / BLOCK_ROWS - 1);
+        assert!(
+            lines[0].starts_with("5         12346789  12346789  | "),
+            "{}",
+            lines[0]
+        );
+        assert_eq!(lines[3], lines[7]);
+        assert!(lines[3].starts_with("---"));
+
+        let bars = |line: &str| -> Vec<usize> {
+            line.char_indices()
+                .filter(|&(_, c)| c == '|' || c == '+')
+                .map(|(k, _)| k)
+                .collect()
+        };
+        for line in &lines {
+            assert_eq!(bars(line), bars(lines[0]), "{}", line);
+        }
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut rng = crate::rng::Rng::new(1);
+        for _ in 0..20 {
+            let solution = crate::generator::random_grid(&mut rng);
+            let bytes = solution.to_bytes();
+            assert_eq!(bytes.len(), 41);
+            assert_eq!(Board::<usize>::from_bytes(&bytes), Ok(solution));
+
+            let problem =
+                crate::generator::generate(25, crate::generator::Symmetry::None, &mut rng);
+            let bytes = problem.to_bytes();
+            assert_eq!(Board::<Option<usize>>::from_bytes(&bytes), Ok(problem));
+        }
+
+        let problem = Board::empty()
+            .with_clue(0, 0, 9)
+            .with_clue(0, 1, 3)
+            .with_clue(8, 8, 1);
+        let bytes = problem.to_bytes();
+        assert_eq!(bytes[0], 0x93);
+        assert_eq!(bytes[40], 0x10);
+        assert_eq!(bytes[1..40], [0; 39]);
+    }
+
+    #[test]
+    fn bytes_errors() {
+        assert_eq!(
+            Board::<Option<usize>>::from_bytes(&[0; 40]),
+            Err(SudokuError::WrongByteLength {
+                expected: 41,
+                found: 40
+            })
+        );
+        let mut bytes = [0; PACKED_LEN];
+        bytes[5] = 0x0A;
+        assert_eq!(
+            Board::<Option<usize>>::from_bytes(&bytes),
+            Err(SudokuError::InvalidValue {
+                line: 2,
+                column: 3,
+                value: "10".to_string()
+            })
+        );
+        bytes[5] = 0x09;
+        assert!(Board::<Option<usize>>::from_bytes(&bytes).is_ok());
+        assert_eq!(
+            Board::<usize>::from_bytes(&bytes),
+            Err(SudokuError::InvalidValue {
+                line: 2,
+                column: 3,
+                value: "9".to_string()
+            })
+        );
+
+        // The last byte holds only the last cell in its high bits.
+        let mut bytes = [0; PACKED_LEN];
+        bytes[PACKED_LEN - 1] = 0x53;
+        assert_eq!(
+            Board::<Option<usize>>::from_bytes(&bytes),
+            Err(SudokuError::NonZeroPadding { nibble: 3 })
+        );
+        assert_eq!(
+            Board::<usize>::from_bytes(&bytes),
+            Err(SudokuError::NonZeroPadding { nibble: 3 })
+        );
+        bytes[PACKED_LEN - 1] = 0x50;
+        assert_eq!(
+            Board::<Option<usize>>::from_bytes(&bytes).unwrap().0[N - 1][N - 1],
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn solution_to_json() {
+        let mut solution = Board([[0; N]; N]);
+        solution.0[0][1] = 8;
+        let json = to_json(&solution);
+        assert!(json.starts_with("[[1,9,1,1,1,1,1,1,1],[1,1,"));
+        assert!(json.ends_with(",1]]"));
+        assert_eq!(json.matches('[').count(), 10);
+    }
+
+    #[test]
+    fn sukaku_round_trip() {
+        let problem = Board::empty().with_clue(0, 0, 5).with_clue(4, 4, 1);
+        let board = crate::solver::propagate(&problem);
+        let values = |board: &Board<SolvingCell>| board.map(|c| c.iter().collect::<Vec<_>>());
+        let parsed = parse_sukaku(&format_candidates(&board)).unwrap();
+        assert_eq!(values(&parsed), values(&board));
+        assert!(parsed.0.iter().flatten().all(SolvingCell::has_update));
+
+        let line = vec!["123456789"; N * N].join(" ");
+        let parsed = parse_sukaku(&format!("# comment\n\n{}\n", line)).unwrap();
+        assert!(parsed.0.iter().flatten().all(|c| c.count() == N));
+    }
+
+    #[test]
+    fn sukaku_errors() {
+        let mut cells = vec!["12"; N * N];
+        assert_eq!(
+            parse_sukaku(&cells[1..].join(" ")),
+            Err(SudokuError::WrongCellCount {
+                expected: N * N,
+                found: N * N - 1
+            })
+        );
+        cells[2] = "305";
+        assert_eq!(
+            parse_sukaku(&format!("\n{}", cells.join(" "))),
+            Err(SudokuError::InvalidCell {
+                line: 2,
+                column: 8,
+                character: '0'
+            })
+        );
+    }
+
+    #[test]
+    fn candidates_as_json() {
+        let problem = Board::empty().with_clue(0, 0, 5).with_clue(4, 4, 1);
+        let board = crate::solver::propagate(&problem);
+        let json = candidates_to_json(&board);
+        assert!(json.starts_with("[[[5],[1,2,3,4,6,7,8,9],"), "{}", json);
+
+        let cells: Vec<Vec<Vec<usize>>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(cells.len(), N);
+        for (i, row) in cells.iter().enumerate() {
+            assert_eq!(row.len(), N);
+            for (j, values) in row.iter().enumerate() {
+                let expected: Vec<usize> = board.0[i][j].iter().map(|n| n + 1).collect();
+                assert_eq!(values, &expected, "({}, {})", i, j);
+            }
+        }
+        assert_eq!(cells[4][4], vec![1]);
+        assert!(!cells[4][0].contains(&1));
+    }
+
+    #[test]
+    fn sdm_puzzles() {
+        let input = format!(
+            "# two puzzles\n{}\n1{}9{}\n\n",
+            "000000010400000000020000000000050407008000300001090000300400200050100000000806000",
+            ".".repeat(75),
+            ".".repeat(4)
+        );
+        let boards = parse_sdm(&input).unwrap();
+        assert_eq!(boards.len(), 2);
+        assert_eq!(boards[0].0[0][7], Some(0));
+        assert_eq!(boards[0].0[1][0], Some(3));
+        assert_eq!(boards[0].0[0][0], None);
+        assert_eq!(boards[1].0[0][0], Some(0));
+        assert_eq!(boards[1].0[8][4], Some(8));
+        assert_eq!(boards[1].0[8][8], None);
+    }
+
+    #[test]
+    fn sdm_wrong_length() {
+        assert_eq!(
+            parse_sdm("#\n123").unwrap_err(),
+            SudokuError::WrongLength {
+                line: 2,
+                expected: 81,
+                found: 3
+            }
+        );
+    }
+
+    #[test]
+    fn sdm_read_line_by_line() {
+        let input = format!("{0}\n# comment\n\n123\n{0}\n", ".".repeat(81));
+        let boards: Vec<_> = read_sdm(input.as_bytes()).collect();
+        assert_eq!(boards.len(), 3);
+        assert_eq!(boards[0].as_ref().unwrap(), &Board::empty());
+        let error = boards[1].as_ref().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "line 4: expected 81 cells, found 3");
+        assert_eq!(boards[2].as_ref().unwrap(), &Board::empty());
+    }
+
+    #[test]
+    fn sdk_puzzle_with_metadata() {
+        let input = "\
+#AWATANABE Yuki
+#DA puzzle
+#B04-10-2026
+1..4..7.9
+.5.78..2.
+7.9.23..6
+3..6.....
+64..7..12
+9.8..2.45
+23.5.48..
+.6..9..3.
+8.7..1.64
+";
+        let boards = parse_sdk(input).unwrap();
+        assert_eq!(boards.len(), 1);
+        assert_eq!(boards[0].0[0][0], Some(0));
+        assert_eq!(boards[0].0[0][1], None);
+        assert_eq!(boards[0].0[8][8], Some(3));
+    }
+
+    #[test]
+    fn sdk_invalid_cell() {
+        assert_eq!(
+            parse_sdk("1..4..7.9\n.5.7x..2.\n").unwrap_err(),
+            SudokuError::InvalidCell {
+                line: 2,
+                column: 5,
+                character: 'x'
+            }
+        );
+    }
+
+    #[test]
+    fn sdk_incomplete() {
+        assert_eq!(
+            parse_sdk("1..4..7.9\n.5.78..2.\n").unwrap_err(),
+            SudokuError::Incomplete { rows: 2 }
+        );
+    }
+
+    /// A killer grid where each row of each block is a cage, with the sums
+    /// after it.
+    fn killer_input(sums: &str) -> String {
+        let letters: Vec<char> = ('A'..='Z').chain('a'..='z').collect();
+        let mut input = String::new();
+        for i in 0..N {
+            for j in 0..N {
+                input.push(letters[i * BLOCK_COLS + j / BLOCK_COLS]);
+            }
+            input.push('\n');
+        }
+        input + sums
+    }
+
+    fn killer_sums() -> String {
+        let letters = ('A'..='Z').chain('a'..='z').take(N * BLOCK_COLS);
+        letters.map(|c| format!("{} = 6\n", c)).collect()
+    }
+
+    #[test]
+    fn killer_cages() {
+        let cages = parse_killer(&killer_input(&killer_sums())).unwrap();
+        assert_eq!(cages.len(), N * BLOCK_COLS);
+        assert_eq!(
+            cages[0],
+            Cage {
+                cells: vec![(0, 0), (0, 1), (0, 2)],
+                sum: 6
+            }
+        );
+        assert_eq!(cages[4].cells, [(1, 3), (1, 4), (1, 5)]);
+        assert!(cages.iter().all(|cage| cage.sum == 6));
+
+        // The sums may come first, and the letters may be spaced.
+        let spaced = killer_input("").replace('A', "A ");
+        let input = format!("# sums\n{}\n{}", killer_sums(), spaced);
+        assert_eq!(parse_killer(&input).unwrap(), cages);
+    }
+
+    #[test]
+    fn killer_errors() {
+        let sums = killer_sums();
+        assert_eq!(
+            parse_killer(&killer_input(&sums.replace("B = 6\n", ""))).unwrap_err(),
+            SudokuError::CageWithoutSum { cage: 'B' }
+        );
+        assert_eq!(
+            parse_killer(&killer_input(&(sums.clone() + "z=3"))).unwrap_err(),
+            SudokuError::CageWithoutCells {
+                line: 37,
+                cage: 'z'
+            }
+        );
+        assert_eq!(
+            parse_killer(&killer_input(&(sums.clone() + "A=7"))).unwrap_err(),
+            SudokuError::DuplicateCage {
+                line: 37,
+                cage: 'A'
+            }
+        );
+        for sum in [5, 25] {
+            let input = killer_input(&sums.replace("C = 6", &format!("C = {}", sum)));
+            assert_eq!(
+                parse_killer(&input).unwrap_err(),
+                SudokuError::InvalidCageSum {
+                    line: 12,
+                    cage: 'C',
+                    cells: 3,
+                    sum
+                }
+            );
+        }
+        assert_eq!(
+            parse_killer(&killer_input(&sums.replace("D = 6", "D = x"))).unwrap_err(),
+            SudokuError::InvalidValue {
+                line: 13,
+                column: 5,
+                value: "x".to_string()
+            }
+        );
+        assert_eq!(
+            parse_killer(&killer_input(&sums.replace("D = 6", "DE = 6"))).unwrap_err(),
+            SudokuError::InvalidCell {
+                line: 13,
+                column: 2,
+                character: 'E'
+            }
+        );
+        assert_eq!(
+            parse_killer(&killer_input(&sums).replacen('A', "1", 1)).unwrap_err(),
+            SudokuError::InvalidCell {
+                line: 1,
+                column: 1,
+                character: '1'
+            }
+        );
+        assert_eq!(
+            parse_killer(&killer_input(&sums).replacen("AAA", "AA", 1)).unwrap_err(),
+            SudokuError::WrongLength {
+                line: 1,
+                expected: N,
+                found: N - 1
+            }
+        );
+        assert_eq!(
+            parse_killer(&sums).unwrap_err(),
+            SudokuError::WrongRowCount {
+                expected: N,
+                found: 0
+            }
+        );
+    }
+}
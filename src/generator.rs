@@ -0,0 +1,469 @@
+//! Puzzle generation.
+
+use crate::board::*;
+use crate::rng::Rng;
+use crate::solver::solutions_bounded;
+use crate::solver::solve_first_random;
+use crate::solver::solve_logically;
+use crate::solver::LogicalOutcome;
+use crate::solver::UniquenessCheck;
+use crate::techniques::Techniques;
+use std::cmp::Reverse;
+
+/// Symmetry of the positions of the clues of a puzzle.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Symmetry {
+    /// No symmetry.
+    #[default]
+    None,
+    /// Unchanged by a half turn.
+    Rotational180,
+    /// Unchanged by a quarter turn.
+    Rotational90,
+    /// Unchanged by flipping the top and bottom halves.
+    Horizontal,
+    /// Unchanged by flipping the left and right halves.
+    Vertical,
+    /// Unchanged by reflection across the main diagonal.
+    Diagonal,
+    /// Unchanged by reflection across the anti-diagonal.
+    AntiDiagonal,
+}
+
+impl Symmetry {
+    /// All symmetries, starting with [`Symmetry::None`].
+    pub const ALL: [Symmetry; 7] = [
+        Symmetry::None,
+        Symmetry::Rotational180,
+        Symmetry::Rotational90,
+        Symmetry::Horizontal,
+        Symmetry::Vertical,
+        Symmetry::Diagonal,
+        Symmetry::AntiDiagonal,
+    ];
+
+    /// Maps a cell to its image under the symmetry.
+    fn image(self, i: usize, j: usize) -> (usize, usize) {
+        match self {
+            Symmetry::None => (i, j),
+            Symmetry::Rotational180 => (N - 1 - i, N - 1 - j),
+            Symmetry::Rotational90 => (j, N - 1 - i),
+            Symmetry::Horizontal => (N - 1 - i, j),
+            Symmetry::Vertical => (i, N - 1 - j),
+            Symmetry::Diagonal => (j, i),
+            Symmetry::AntiDiagonal => (N - 1 - j, N - 1 - i),
+        }
+    }
+
+    /// Cells that must be all clues or all empty together with `(i, j)`,
+    /// including `(i, j)` itself.
+    pub fn orbit(self, i: usize, j: usize) -> Vec<(usize, usize)> {
+        let mut cells = vec![(i, j)];
+        loop {
+            let &(i2, j2) = cells.last().unwrap();
+            let next = self.image(i2, j2);
+            if next == (i, j) {
+                return cells;
+            }
+            cells.push(next);
+        }
+    }
+}
+
+/// Returns the symmetries that the positions of the clues of `problem` have,
+/// in the order of [`Symmetry::ALL`].
+///
+/// [`Symmetry::None`] is left out, so the result is empty for an asymmetric
+/// puzzle. The values of the clues do not matter.
+pub fn detect_symmetry(problem: &Board<Option<usize>>) -> Vec<Symmetry> {
+    let mask = clue_mask(problem);
+    IntoIterator::into_iter(Symmetry::ALL)
+        .filter(|&symmetry| symmetry != Symmetry::None)
+        .filter(|&symmetry| {
+            (0..N * N).all(|k| {
+                let (i, j) = symmetry.image(k / N, k % N);
+                mask.0[i][j] == mask.0[k / N][k % N]
+            })
+        })
+        .collect()
+}
+
+/// Number of clues in `problem`.
+pub fn clue_count(problem: &Board<Option<usize>>) -> usize {
+    problem.0.iter().flatten().filter(|v| v.is_some()).count()
+}
+
+/// Number of clues of each value in `problem`, where index `n` is for value
+/// `n` (0-based).
+pub fn clue_digit_counts(problem: &Board<Option<usize>>) -> [usize; N] {
+    let mut counts = [0; N];
+    for &n in problem.0.iter().flatten().flatten() {
+        counts[n] += 1;
+    }
+    counts
+}
+
+/// Returns a random complete grid.
+pub fn random_grid(rng: &mut Rng) -> Board<usize> {
+    // Every board size has a solution of the empty board.
+    solve_first_random(&Board([[None; N]; N]), rng).unwrap()
+}
+
+/// Options that control what puzzles are generated.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GeneratorOptions {
+    /// Number of clues to aim for. The result may have more clues if no more
+    /// can be removed.
+    pub clues: usize,
+    /// Symmetry of the positions of the clues.
+    pub symmetry: Symmetry,
+    /// If set, only puzzles that [`solve_logically`] completes with these
+    /// techniques, that is, without guessing, are generated.
+    pub logical: Option<Techniques>,
+    /// Whether to prefer removing clues of the values that appear most, so
+    /// that the values are evenly distributed among the clues.
+    pub balanced: bool,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            clues: 28,
+            symmetry: Symmetry::None,
+            logical: None,
+            balanced: false,
+        }
+    }
+}
+
+/// Removes clues from `problem` in random order, keeping it uniquely
+/// solvable, until it has no more than `options.clues` clues or no more clues
+/// can be removed.
+fn reduce(
+    mut problem: Board<Option<usize>>,
+    options: &GeneratorOptions,
+    rng: &mut Rng,
+) -> Board<Option<usize>> {
+    // No clue can be removed from a puzzle that is not uniquely solvable.
+    let check = match solutions_bounded(&problem, 2) {
+        (solutions, false) if solutions.len() == 1 => UniquenessCheck::new(solutions[0]),
+        _ => return problem,
+    };
+    let mut cells: Vec<(usize, usize)> = (0..N * N).map(|k| (k / N, k % N)).collect();
+    rng.shuffle(&mut cells);
+
+    let mut clues = clue_count(&problem);
+    while clues > options.clues && !cells.is_empty() {
+        if options.balanced {
+            // The sort is stable, so ties stay in random order.
+            let counts = clue_digit_counts(&problem);
+            cells.sort_by_key(|&(i, j)| Reverse(problem.0[i][j].map_or(0, |n| counts[n])));
+        }
+        let (i, j) = cells.remove(0);
+        if problem.0[i][j].is_none() {
+            continue;
+        }
+
+        let orbit = options.symmetry.orbit(i, j);
+        let mut reduced = problem;
+        for &(i2, j2) in &orbit {
+            reduced.0[i2][j2] = None;
+        }
+        // A clue whose removal would make guessing necessary is kept, and
+        // other clues are tried instead.
+        let logical = options.logical.as_ref().is_none_or(|techniques| {
+            matches!(
+                solve_logically(&reduced, techniques),
+                LogicalOutcome::Solved(_)
+            )
+        });
+        if logical && check.still_unique(&reduced, &orbit) {
+            clues = clue_count(&reduced);
+            problem = reduced;
+        }
+    }
+    problem
+}
+
+/// Removes clues from a uniquely solvable `problem` until no more can be
+/// removed without losing uniqueness or the clue pattern's symmetry.
+///
+/// With [`Symmetry::None`], the result is minimal in that every clue is
+/// needed.
+pub fn minimize(
+    problem: &Board<Option<usize>>,
+    symmetry: Symmetry,
+    rng: &mut Rng,
+) -> Board<Option<usize>> {
+    let options = GeneratorOptions {
+        clues: 0,
+        symmetry,
+        ..GeneratorOptions::default()
+    };
+    reduce(*problem, &options, rng)
+}
+
+/// Generates a uniquely solvable puzzle with the given clue symmetry.
+///
+/// Clues are removed from a random complete grid until no more than `clues`
+/// remain. The result may have more clues than requested if no more can be
+/// removed without losing uniqueness.
+pub fn generate(clues: usize, symmetry: Symmetry, rng: &mut Rng) -> Board<Option<usize>> {
+    let options = GeneratorOptions {
+        clues,
+        symmetry,
+        ..GeneratorOptions::default()
+    };
+    generate_with(&options, rng)
+}
+
+/// Like [`generate`], but generates a puzzle as configured by `options`.
+pub fn generate_with(options: &GeneratorOptions, rng: &mut Rng) -> Board<Option<usize>> {
+    let grid = random_grid(rng).map(|&n| Some(n));
+    reduce(grid, options, rng)
+}
+
+/// Returns a random permutation of the rows or columns that keeps the rows
+/// or columns of each block together, where index `k` of the result is the
+/// original index of the `k`th line. `size` is the number of the lines in a
+/// block.
+fn line_permutation(size: usize, rng: &mut Rng) -> Vec<usize> {
+    let mut bands: Vec<usize> = (0..N / size).collect();
+    rng.shuffle(&mut bands);
+    let mut lines = Vec::with_capacity(N);
+    for band in bands {
+        let mut band_lines: Vec<usize> = (band * size..(band + 1) * size).collect();
+        rng.shuffle(&mut band_lines);
+        lines.extend(band_lines);
+    }
+    lines
+}
+
+/// Returns a puzzle equivalent to `problem` that looks different.
+///
+/// The values are relabeled, the bands and stacks are permuted, and so are
+/// the rows and columns within each, and the board may be transposed. Since
+/// these transformations map the rules onto themselves, the result has as
+/// many solutions as `problem` and takes the same deductions to solve.
+/// Rotations and reflections are among the combinations.
+pub fn scramble(problem: &Board<Option<usize>>, rng: &mut Rng) -> Board<Option<usize>> {
+    let mut values: Vec<usize> = (0..N).collect();
+    rng.shuffle(&mut values);
+    let rows = line_permutation(BLOCK_ROWS, rng);
+    let cols = line_permutation(BLOCK_COLS, rng);
+    // Transposing rectangular blocks would not keep them blocks.
+    let transpose = BLOCK_ROWS == BLOCK_COLS && rng.below(2) == 1;
+
+    let mut scrambled = Board::empty();
+    for (i, &row) in rows.iter().enumerate() {
+        for (j, &col) in cols.iter().enumerate() {
+            let (i2, j2) = if transpose { (col, row) } else { (row, col) };
+            scrambled.0[i][j] = problem.0[i2][j2].map(|n| values[n]);
+        }
+    }
+    scrambled
+}
+
+// The tests are written for the 9x9 board.
+#[cfg(all(test, not(any(board_4x4, board_6x6, board_16x16, board_25x25))))]
+mod tests {
+
+    use super::*;
+    use crate::solver::is_minimal;
+    use crate::solver::is_uniquely_solvable;
+    use crate::solver::solve_first;
+
+    #[test]
+    fn orbits() {
+        assert_eq!(Symmetry::None.orbit(1, 2), vec![(1, 2)]);
+        assert_eq!(Symmetry::Rotational180.orbit(1, 2), vec![(1, 2), (7, 6)]);
+        assert_eq!(Symmetry::Rotational180.orbit(4, 4), vec![(4, 4)]);
+        assert_eq!(
+            Symmetry::Rotational90.orbit(0, 1),
+            vec![(0, 1), (1, 8), (8, 7), (7, 0)]
+        );
+        assert_eq!(Symmetry::Diagonal.orbit(3, 3), vec![(3, 3)]);
+        assert_eq!(Symmetry::AntiDiagonal.orbit(0, 0), vec![(0, 0), (8, 8)]);
+    }
+
+    #[test]
+    fn detecting_symmetry() {
+        assert_eq!(detect_symmetry(&Board::empty()), &Symmetry::ALL[1..]);
+        let corner = Board::empty().with_clue(0, 0, 1);
+        assert_eq!(detect_symmetry(&corner), [Symmetry::Diagonal]);
+        let corners = corner.with_clue(N - 1, N - 1, 2);
+        assert_eq!(
+            detect_symmetry(&corners),
+            [
+                Symmetry::Rotational180,
+                Symmetry::Diagonal,
+                Symmetry::AntiDiagonal
+            ]
+        );
+        let edge = Board::empty().with_clue(0, 1, 1).with_clue(N - 1, 1, 1);
+        assert_eq!(detect_symmetry(&edge), [Symmetry::Horizontal]);
+
+        for symmetry in &Symmetry::ALL[1..] {
+            let problem = generate(30, *symmetry, &mut Rng::new(3));
+            assert!(
+                detect_symmetry(&problem).contains(symmetry),
+                "{:?}",
+                symmetry
+            );
+        }
+    }
+
+    #[test]
+    fn generate_symmetric_unique() {
+        let mut rng = Rng::new(42);
+        let problem = generate(30, Symmetry::Rotational180, &mut rng);
+        assert!(is_uniquely_solvable(&problem));
+        for i in 0..N {
+            for j in 0..N {
+                let (i2, j2) = Symmetry::Rotational180.image(i, j);
+                assert_eq!(problem.0[i][j].is_some(), problem.0[i2][j2].is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn generate_logically_solvable() {
+        for seed in 0..5 {
+            let options = GeneratorOptions {
+                clues: 20,
+                logical: Some(Techniques::default()),
+                ..GeneratorOptions::default()
+            };
+            let problem = generate_with(&options, &mut Rng::new(seed));
+            assert!(
+                matches!(
+                    solve_logically(&problem, &Techniques::default()),
+                    LogicalOutcome::Solved(_)
+                ),
+                "seed {}",
+                seed
+            );
+            assert!(is_uniquely_solvable(&problem), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn digit_counts() {
+        let problem = Board::empty()
+            .with_clue(0, 0, 1)
+            .with_clue(1, 3, 1)
+            .with_clue(8, 8, 9);
+        assert_eq!(clue_digit_counts(&problem), [2, 0, 0, 0, 0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn generate_balanced() {
+        let spread = |problem: &Board<Option<usize>>| {
+            let counts = clue_digit_counts(problem);
+            counts.iter().max().unwrap() - counts.iter().min().unwrap()
+        };
+        let (mut balanced, mut unbalanced) = (0, 0);
+        for seed in 0..8 {
+            let options = GeneratorOptions {
+                clues: 26,
+                balanced: true,
+                ..GeneratorOptions::default()
+            };
+            let problem = generate_with(&options, &mut Rng::new(seed));
+            assert!(is_uniquely_solvable(&problem), "seed {}", seed);
+            balanced += spread(&problem);
+            unbalanced += spread(&generate(26, Symmetry::None, &mut Rng::new(seed)));
+        }
+        assert!(balanced < unbalanced, "{} >= {}", balanced, unbalanced);
+    }
+
+    #[test]
+    fn generate_is_reproducible() {
+        let a = generate(28, Symmetry::None, &mut Rng::new(5));
+        let b = generate(28, Symmetry::None, &mut Rng::new(5));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn minimize_is_minimal() {
+        let mut rng = Rng::new(3);
+        let problem = generate(40, Symmetry::None, &mut rng);
+        assert!(!is_minimal(&problem));
+        assert!(is_minimal(&minimize(&problem, Symmetry::None, &mut rng)));
+    }
+
+    #[test]
+    fn generated_puzzles_are_uniquely_solvable() {
+        for seed in 0..12 {
+            for &clues in &[22, 28, 34] {
+                let symmetry = [Symmetry::None, Symmetry::Rotational180, Symmetry::Diagonal]
+                    [seed as usize % 3];
+                let problem = generate(clues, symmetry, &mut Rng::new(seed));
+                assert!(is_uniquely_solvable(&problem), "seed {}", seed);
+
+                let solution = solve_first(&problem).unwrap();
+                assert!(solution.is_valid_complete(), "seed {}", seed);
+                for i in 0..N {
+                    for j in 0..N {
+                        if let Some(n) = problem.0[i][j] {
+                            assert_eq!(solution.0[i][j], n, "seed {}", seed);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn random_grids_are_valid() {
+        for seed in 0..20 {
+            assert!(random_grid(&mut Rng::new(seed)).is_valid_complete());
+        }
+    }
+
+    #[test]
+    fn minimized_puzzles_are_minimal() {
+        for seed in 0..6 {
+            let mut rng = Rng::new(seed);
+            let problem = minimize(
+                &generate(36, Symmetry::None, &mut rng),
+                Symmetry::None,
+                &mut rng,
+            );
+            assert!(is_minimal(&problem), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn scrambled_puzzles_are_equivalent() {
+        let options = GeneratorOptions {
+            logical: Some(Techniques::default()),
+            ..GeneratorOptions::default()
+        };
+        let problem = generate_with(&options, &mut Rng::new(9));
+        let mut counts = clue_digit_counts(&problem);
+        counts.sort_unstable();
+        for seed in 0..8 {
+            let scrambled = scramble(&problem, &mut Rng::new(seed));
+            assert_ne!(scrambled, problem, "seed {}", seed);
+            assert_eq!(
+                clue_count(&scrambled),
+                clue_count(&problem),
+                "seed {}",
+                seed
+            );
+            let mut scrambled_counts = clue_digit_counts(&scrambled);
+            scrambled_counts.sort_unstable();
+            assert_eq!(scrambled_counts, counts, "seed {}", seed);
+            assert!(is_uniquely_solvable(&scrambled), "seed {}", seed);
+            assert!(
+                matches!(
+                    solve_logically(&scrambled, &Techniques::default()),
+                    LogicalOutcome::Solved(_)
+                ),
+                "seed {}",
+                seed
+            );
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! A simple sudoku solver.
+
+/// Asserts an internal invariant of the solver.
+///
+/// Like `debug_assert!`, this is checked in debug builds. With the
+/// `check-invariants` feature, it is also checked in release builds, so that a
+/// bug in propagation or a technique panics instead of yielding a wrong
+/// solution.
+macro_rules! invariant {
+    ($($arg:tt)*) => {
+        if cfg!(any(debug_assertions, feature = "check-invariants")) {
+            assert!($($arg)*);
+        }
+    };
+}
+
+pub mod board;
+pub mod dlx;
+pub mod error;
+pub mod format;
+pub mod generator;
+pub mod rng;
+pub mod solver;
+pub mod steps;
+pub mod techniques;
+pub mod trace;
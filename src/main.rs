@@ -1,45 +1,464 @@
-mod board;
-mod solver;
-
-use board::*;
-use solver::for_each_solution;
+use std::collections::VecDeque;
+use std::env::args;
+use std::fmt::Write;
+use std::fs::File;
 use std::io::stdin;
-use std::io::BufRead;
+use std::io::stdout;
+use std::io::BufReader;
+use std::io::BufWriter;
 use std::io::Error;
 use std::io::ErrorKind;
+use std::io::Read as _;
 use std::io::Result;
+use std::io::Write as _;
+use std::process::ExitCode;
+use std::sync::Mutex;
+use std::thread::available_parallelism;
+use std::time::Instant;
+use std::time::SystemTime;
+use sudoku_solver_in_rust::board::*;
+use sudoku_solver_in_rust::format::format_answer_key;
+use sudoku_solver_in_rust::format::format_filled;
+use sudoku_solver_in_rust::format::parse_killer;
+use sudoku_solver_in_rust::format::read_problem;
+use sudoku_solver_in_rust::format::read_sdm;
+use sudoku_solver_in_rust::format::to_json;
+use sudoku_solver_in_rust::format::write_solution;
+use sudoku_solver_in_rust::format::Emphasis;
+use sudoku_solver_in_rust::format::Marking;
+use sudoku_solver_in_rust::generator::clue_count;
+use sudoku_solver_in_rust::generator::detect_symmetry;
+use sudoku_solver_in_rust::generator::generate_with;
+use sudoku_solver_in_rust::generator::GeneratorOptions;
+use sudoku_solver_in_rust::generator::Symmetry;
+use sudoku_solver_in_rust::rng::Rng;
+use sudoku_solver_in_rust::solver::conflicts;
+use sudoku_solver_in_rust::solver::for_each_solution_with_stats;
+use sudoku_solver_in_rust::solver::search_tree;
+use sudoku_solver_in_rust::solver::solve_first_each;
+use sudoku_solver_in_rust::solver::verify_solution;
+use sudoku_solver_in_rust::solver::Options;
+use sudoku_solver_in_rust::steps::solving_frames;
+use sudoku_solver_in_rust::techniques::Techniques;
+
+fn invalid_input(message: String) -> Error {
+    Error::new(ErrorKind::InvalidInput, message)
+}
+
+/// Why a puzzle has no solution, which decides the exit status.
+#[derive(Debug)]
+enum Unsolved {
+    /// Some clues repeat a value in a row, column, or block, so the input
+    /// is likely mistyped.
+    ConflictingClues(Vec<((usize, usize), (usize, usize))>),
+    /// The clues are consistent with each other, but there is no solution.
+    NoSolution,
+}
+
+impl Unsolved {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Unsolved::NoSolution => 2,
+            Unsolved::ConflictingClues(_) => 3,
+        }
+    }
+}
+
+impl std::fmt::Display for Unsolved {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Unsolved::ConflictingClues(pairs) => {
+                f.write_str("conflicting clues:")?;
+                for (k, ((i1, j1), (i2, j2))) in pairs.iter().enumerate() {
+                    let separator = if k == 0 { " " } else { ", " };
+                    write!(
+                        f,
+                        "{}r{}c{} and r{}c{}",
+                        separator,
+                        i1 + 1,
+                        j1 + 1,
+                        i2 + 1,
+                        j2 + 1
+                    )?;
+                }
+                Ok(())
+            }
+            Unsolved::NoSolution => f.write_str("no solution"),
+        }
+    }
+}
+
+impl std::error::Error for Unsolved {}
+
+/// Returns the error for `problem`, which has no solution.
+fn unsolved(problem: &Board<Option<usize>>) -> Error {
+    let pairs = conflicts(problem);
+    if pairs.is_empty() {
+        Error::other(Unsolved::NoSolution)
+    } else {
+        Error::other(Unsolved::ConflictingClues(pairs))
+    }
+}
+
+/// Returns the value following an option in the arguments.
+fn option_value<'a, I>(name: &str, args: &mut I) -> Result<&'a str>
+where
+    I: Iterator<Item = &'a String>,
+{
+    args.next()
+        .map(String::as_str)
+        .ok_or_else(|| invalid_input(format!("missing value for {}", name)))
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| invalid_input(format!("invalid value for {}: {}", name, value)))
+}
+
+fn parse_symmetry(value: &str) -> Result<Symmetry> {
+    match value {
+        "none" => Ok(Symmetry::None),
+        "rot180" => Ok(Symmetry::Rotational180),
+        "rot90" => Ok(Symmetry::Rotational90),
+        "horizontal" => Ok(Symmetry::Horizontal),
+        "vertical" => Ok(Symmetry::Vertical),
+        "diagonal" => Ok(Symmetry::Diagonal),
+        "antidiagonal" => Ok(Symmetry::AntiDiagonal),
+        _ => Err(invalid_input(format!("unknown symmetry: {}", value))),
+    }
+}
+
+/// Returns the name of `symmetry` accepted by [`parse_symmetry`].
+fn symmetry_name(symmetry: Symmetry) -> &'static str {
+    match symmetry {
+        Symmetry::None => "none",
+        Symmetry::Rotational180 => "rot180",
+        Symmetry::Rotational90 => "rot90",
+        Symmetry::Horizontal => "horizontal",
+        Symmetry::Vertical => "vertical",
+        Symmetry::Diagonal => "diagonal",
+        Symmetry::AntiDiagonal => "antidiagonal",
+    }
+}
+
+fn print_puzzle(puzzle: &Board<Option<usize>>, line: bool) {
+    let digit = |v: &Option<usize>| v.map_or(0, |n| n + 1);
+    if line {
+        for row in puzzle {
+            for v in row {
+                print!("{}", digit(v));
+            }
+        }
+        println!();
+    } else {
+        print!("{}", puzzle);
+    }
+}
+
+/// Generates a puzzle as specified by the arguments after `generate`.
+fn generate_command(args: &[String]) -> Result<()> {
+    let mut options = GeneratorOptions::default();
+    let mut seed = None;
+    let mut line = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--clues" => {
+                options.clues = parse_number("--clues", option_value("--clues", &mut args)?)?
+            }
+            "--symmetry" => {
+                options.symmetry = parse_symmetry(option_value("--symmetry", &mut args)?)?
+            }
+            "--seed" => seed = Some(parse_number("--seed", option_value("--seed", &mut args)?)?),
+            "--logical" => options.logical = Some(Techniques::default()),
+            "--balanced" => options.balanced = true,
+            "--line" => line = true,
+            _ => return Err(invalid_input(format!("unknown argument: {}", arg))),
+        }
+    }
+
+    let seed = seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64)
+    });
+    let puzzle = generate_with(&options, &mut Rng::new(seed));
+    print_puzzle(&puzzle, line);
+    Ok(())
+}
 
-fn eof() -> Error {
-    Error::new(ErrorKind::UnexpectedEof, "malformed problem")
+/// How `solve` prints the solutions.
+#[derive(Clone, Copy)]
+enum OutputFormat {
+    Plain,
+    Json { clues: usize },
+    Marked(Marking),
+    AnswerKey(Emphasis),
 }
 
-fn read_problem() -> Result<Board<Option<usize>>> {
-    let mut board = Board([[None; N]; N]);
-    let input = stdin();
-    let mut lines = input.lock().lines();
-    for i in 0..N {
-        let line = lines.next().unwrap_or_else(|| Err(eof()))?;
-        let mut line = line.chars().filter_map(|c| c.to_digit(10));
-        for j in 0..N {
-            let n = line.next().ok_or_else(eof)?;
-            board.0[i][j] = if n == 0 { None } else { Some((n - 1) as usize) }
+/// Writes a solution of `problem` to `w` in `format`.
+fn write_output<W: Write>(
+    w: &mut W,
+    problem: &Board<Option<usize>>,
+    solution: &Board<usize>,
+    format: OutputFormat,
+) -> std::fmt::Result {
+    match format {
+        OutputFormat::Plain => write_solution(w, solution),
+        OutputFormat::Json { clues } => write_json(w, Some(solution), clues),
+        OutputFormat::Marked(marking) => {
+            writeln!(w, "{}", format_filled(problem, solution, marking))
+        }
+        OutputFormat::AnswerKey(emphasis) => {
+            writeln!(w, "{}", format_answer_key(problem, solution, emphasis))
         }
     }
-    Ok(board)
 }
 
-fn main() -> Result<()> {
-    let board = read_problem()?;
+/// Writes `solution`, or `null` if there is none, as a JSON object on one line
+/// with the number of clues in the puzzle.
+fn write_json<W: Write>(
+    w: &mut W,
+    solution: Option<&Board<usize>>,
+    clues: usize,
+) -> std::fmt::Result {
+    let solution = solution.map_or_else(|| "null".to_string(), to_json);
+    writeln!(w, r#"{{"solution":{},"clues":{}}}"#, solution, clues)
+}
+
+/// Solves a puzzle read from the standard input.
+fn solve_command(args: &[String]) -> Result<()> {
+    let mut print_stats = false;
+    let mut json = false;
+    let mut dot = false;
+    let mut frames = false;
+    let mut killer = false;
+    let mut print_symmetry = false;
+    let mut marking = None;
+    let mut answer_key = false;
+    for arg in args {
+        match arg.as_str() {
+            "--mark-filled" => marking = Some(Marking::Parentheses),
+            "--color" => marking = Some(Marking::Color),
+            "--answer-key" => answer_key = true,
+            "--stats" => print_stats = true,
+            "--json" => json = true,
+            "--dot" => dot = true,
+            "--frames" => frames = true,
+            "--killer" => killer = true,
+            "--detect-symmetry" => print_symmetry = true,
+            _ => return Err(invalid_input(format!("unknown argument: {}", arg))),
+        }
+    }
+
+    // With --answer-key, --color makes the givens bold instead of coloring the
+    // filled cells.
+    let emphasis = match (answer_key, marking) {
+        (false, _) => None,
+        (true, None) => Some(Emphasis::Brackets),
+        (true, Some(Marking::Color)) => Some(Emphasis::Bold),
+        (true, Some(Marking::Parentheses)) => {
+            return Err(invalid_input(
+                "--mark-filled cannot be used with --answer-key".to_string(),
+            ))
+        }
+    };
+
+    let mut options = Options::default();
+    let board = if killer {
+        if frames {
+            return Err(invalid_input(
+                "--frames cannot be used with --killer".to_string(),
+            ));
+        }
+        let mut input = String::new();
+        stdin().lock().read_to_string(&mut input)?;
+        options.cages = parse_killer(&input).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        Board::empty()
+    } else {
+        read_problem(stdin().lock())?
+    };
+    if print_symmetry {
+        let names: Vec<&str> = detect_symmetry(&board)
+            .into_iter()
+            .map(symmetry_name)
+            .collect();
+        let names = if names.is_empty() {
+            vec!["none"]
+        } else {
+            names
+        };
+        eprintln!("symmetry: {}", names.join(" "));
+    }
+    if dot {
+        print!("{}", search_tree(&board, &options).to_dot());
+        return Ok(());
+    }
+    if frames {
+        let frames =
+            solving_frames(&board, &Techniques::default()).ok_or_else(|| unsolved(&board))?;
+        for (k, frame) in frames.iter().enumerate() {
+            if k > 0 {
+                println!();
+            }
+            print!("{}", frame);
+        }
+        return Ok(());
+    }
+    let format = if json {
+        OutputFormat::Json {
+            clues: clue_count(&board),
+        }
+    } else if let Some(emphasis) = emphasis {
+        OutputFormat::AnswerKey(emphasis)
+    } else if let Some(marking) = marking {
+        OutputFormat::Marked(marking)
+    } else {
+        OutputFormat::Plain
+    };
     let mut found_solution = false;
+    let mut output = String::new();
 
-    for_each_solution(&board, |b| {
+    let start = Instant::now();
+    let stats = for_each_solution_with_stats(&board, &options, |b| {
         found_solution = true;
-        println!("{}", b);
+        output.clear();
+        write_output(&mut output, &board, &b, format).unwrap();
+        print!("{}", output);
     });
+    let elapsed = start.elapsed();
+
+    if print_stats {
+        eprintln!("guesses: {}", stats.guesses);
+        eprintln!("max depth: {}", stats.max_depth);
+        eprintln!("sweeps: {}", stats.sweeps);
+        eprintln!("time: {:?}", elapsed);
+    }
 
     if found_solution {
         Ok(())
     } else {
-        Err(Error::new(ErrorKind::Other, "no solution"))
+        Err(unsolved(&board))
+    }
+}
+
+/// Reads a puzzle from the file at `path`.
+fn read_file(path: &str) -> Result<Board<Option<usize>>> {
+    let file = File::open(path).map_err(|e| Error::new(e.kind(), format!("{}: {}", path, e)))?;
+    read_problem(BufReader::new(file)).map_err(|e| Error::new(e.kind(), format!("{}: {}", path, e)))
+}
+
+/// Checks the solution in the file given as the second argument against the
+/// puzzle in the first.
+fn verify_command(args: &[String]) -> Result<()> {
+    let (puzzle, solution) = match args {
+        [puzzle, solution] => (read_file(puzzle)?, read_file(solution)?),
+        _ => return Err(invalid_input("usage: --verify PUZZLE SOLUTION".to_string())),
+    };
+    let empty = solution.cells_where(Option::is_none);
+    if let Some(&(i, j)) = empty.first() {
+        return Err(Error::other(format!("r{}c{} is empty", i + 1, j + 1)));
+    }
+    match verify_solution(&puzzle, &solution.map(|cell| cell.unwrap())) {
+        Ok(()) => {
+            println!("ok");
+            Ok(())
+        }
+        Err(mismatch) => Err(Error::other(mismatch.to_string())),
+    }
+}
+
+/// Solves each line of the standard input as a puzzle in the `.sdm` format
+/// and prints the solutions, one per line in the same order, as JSON records
+/// with `--json`.
+fn batch_command(args: &[String]) -> Result<()> {
+    let mut threads = None;
+    let mut json = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threads" => {
+                threads = Some(parse_number(
+                    "--threads",
+                    option_value("--threads", &mut args)?,
+                )?)
+            }
+            "--json" => json = true,
+            _ => return Err(invalid_input(format!("unknown argument: {}", arg))),
+        }
+    }
+    let threads = match threads {
+        Some(0) => return Err(invalid_input("--threads must be positive".to_string())),
+        Some(threads) => threads,
+        None => available_parallelism().map_or(1, usize::from),
+    };
+
+    // The puzzles are read until the first invalid one, which is reported
+    // after the solutions of the preceding ones are printed.
+    let read_error = Mutex::new(None);
+    // The clue counts of the puzzles read but not yet printed, in order.
+    let clues = Mutex::new(VecDeque::new());
+    let problems = read_sdm(BufReader::new(stdin())).map_while(|problem| {
+        let problem = problem
+            .map_err(|e| *read_error.lock().unwrap() = Some(e))
+            .ok()?;
+        clues.lock().unwrap().push_back(clue_count(&problem));
+        Some(problem)
+    });
+    let mut output = BufWriter::new(stdout().lock());
+    let mut write_result = Ok(());
+    solve_first_each(problems, threads, |solution| {
+        let clues = clues.lock().unwrap().pop_front().unwrap();
+        if write_result.is_ok() {
+            write_result = match solution {
+                _ if json => {
+                    let mut record = String::new();
+                    write_json(&mut record, solution.as_ref(), clues).unwrap();
+                    output.write_all(record.as_bytes())
+                }
+                Some(solution) => {
+                    let digits: String = solution
+                        .0
+                        .iter()
+                        .flatten()
+                        .map(|n| char::from(b'1' + *n as u8))
+                        .collect();
+                    writeln!(output, "{}", digits)
+                }
+                None => writeln!(output, "no solution"),
+            };
+        }
+    });
+    write_result?;
+    output.flush()?;
+    match read_error.into_inner().unwrap() {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("generate") => generate_command(&args[1..]),
+        Some("--verify") => verify_command(&args[1..]),
+        Some("--batch") => batch_command(&args[1..]),
+        _ => solve_command(&args),
+    };
+    let error = match result {
+        Ok(()) => return ExitCode::SUCCESS,
+        Err(error) => error,
+    };
+    match error.get_ref().and_then(|e| e.downcast_ref::<Unsolved>()) {
+        Some(unsolved) => {
+            eprintln!("{}", unsolved);
+            ExitCode::from(unsolved.exit_code())
+        }
+        None => {
+            // The same report as returning the error from `main`.
+            eprintln!("Error: {:?}", error);
+            ExitCode::FAILURE
+        }
     }
 }
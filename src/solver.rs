@@ -1,162 +1,3033 @@
 use crate::board::*;
+use crate::rng::Rng;
+use crate::techniques::Techniques;
+use crate::trace::Node;
+use crate::trace::Outcome;
+use crate::trace::SearchTree;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::ops::ControlFlow;
+use std::sync::mpsc::sync_channel;
+use std::sync::mpsc::Receiver;
+use std::sync::Mutex;
+use std::thread;
 
-fn examine_area<I>(board: &mut Board<SolvingCell>, i: I) -> bool
+/// Work left for propagation.
+///
+/// A cell is pending while its update flag is set, that is, its candidates
+/// have changed since it was examined. A unit is pending if any of its cells
+/// has changed since the unit was searched for hidden singles. Units of cells
+/// that had been examined before propagation started are assumed to have been
+/// searched, which holds for boards left by propagation and for new boards.
+struct Pending {
+    /// Bit `i * N + j` is for cell `(i, j)`, counting from the first word.
+    cells: [u128; CELL_WORDS],
+    /// Bits 0 to 8 are for the rows, 9 to 17 for the columns, and 18 to 26
+    /// for the blocks in row-major order.
+    units: u128,
+    /// Whether the extra regions need to be searched. They are not tracked
+    /// one by one.
+    regions: bool,
+}
+
+/// Number of words for the cells in `Pending`.
+const CELL_WORDS: usize = (N * N).div_ceil(128);
+
+// The units must fit in `Pending`.
+const _: () = assert!(3 * N <= 128);
+
+impl Pending {
+    /// Collects the cells of `board` whose update flag is set.
+    fn new(board: &Board<SolvingCell>) -> Self {
+        let mut pending = Pending::empty();
+        pending.mark_updated(board);
+        pending
+    }
+
+    fn empty() -> Self {
+        Pending {
+            cells: [0; CELL_WORDS],
+            units: 0,
+            regions: false,
+        }
+    }
+
+    /// Marks `(i, j)` and its units as pending.
+    fn mark(&mut self, i: usize, j: usize) {
+        let k = i * N + j;
+        self.cells[k / 128] |= 1 << (k % 128);
+        let block = i / BLOCK_ROWS * (N / BLOCK_COLS) + j / BLOCK_COLS;
+        self.units |= 1 << i | 1 << (N + j) | 1 << (2 * N + block);
+        self.regions = true;
+    }
+
+    /// Marks the cells of `board` whose update flag is set.
+    fn mark_updated(&mut self, board: &Board<SolvingCell>) {
+        for (i, j) in (0..N).flat_map(|i| (0..N).map(move |j| (i, j))) {
+            if board.0[i][j].has_update() {
+                self.mark(i, j);
+            }
+        }
+    }
+
+    /// Removes and returns the first pending cell.
+    fn pop_cell(&mut self) -> Option<(usize, usize)> {
+        let w = self.cells.iter().position(|&word| word != 0)?;
+        let k = w * 128 + self.cells[w].trailing_zeros() as usize;
+        self.cells[w] &= self.cells[w] - 1;
+        Some((k / N, k % N))
+    }
+
+    /// Unit of bit `k` of `units`.
+    fn unit(k: usize) -> Unit {
+        match k / N {
+            0 => Unit::Row(k),
+            1 => Unit::Col(k - N),
+            _ => Unit::Block(
+                (k - 2 * N) / (N / BLOCK_COLS),
+                (k - 2 * N) % (N / BLOCK_COLS),
+            ),
+        }
+    }
+}
+
+fn examine_area<I>(board: &mut Board<SolvingCell>, cells: I, pending: &mut Pending) -> bool
 where
     I: Iterator<Item = (usize, usize)> + Clone,
 {
+    // Values that some cells of the area can be, and that more than one can.
+    let full = SolvingCell::new(None);
+    let (mut once, mut twice) = (full.difference(&full), full.difference(&full));
+    for (i2, j2) in cells.clone() {
+        let cell = &board.0[i2][j2];
+        twice = twice.union(&once.intersect(cell));
+        once = once.union(cell);
+    }
+
     let mut has_update = false;
-    'n: for n in 0..N {
-        // Find cells that can be n.
-        let mut found_cell = None;
-        for (i2, j2) in i.clone() {
-            if board.0[i2][j2].can_be(n) {
-                match found_cell {
-                    None => found_cell = Some((i2, j2)),
-                    Some(_) => continue 'n,
-                }
-            }
-        }
+    for n in once.difference(&twice).iter() {
+        // An earlier value may have taken the only cell that could be n, in
+        // which case the contradiction is left to the next sweep.
+        let found_cell = cells.clone().find(|&(i2, j2)| board.0[i2][j2].can_be(n));
 
         // If there's exactly one such cell, make it unique.
         if let Some((i2, j2)) = found_cell {
             match board.0[i2][j2].get_unique() {
                 None => {
+                    #[cfg(feature = "log")]
+                    log::trace!("hidden single: r{}c{} = {}", i2 + 1, j2 + 1, n + 1);
                     board.0[i2][j2] = SolvingCell::new(Some(n));
+                    pending.mark(i2, j2);
                     has_update = true;
                 }
-                Some(n2) => debug_assert_eq!(n, n2),
+                // The only cell that can take `n` must not be determined as
+                // another value, or the unit would have no place for `n`
+                // without this being noticed.
+                Some(n2) => invariant!(
+                    n == n2,
+                    "r{}c{} is {} but is the only place for {}",
+                    i2 + 1,
+                    j2 + 1,
+                    n2 + 1,
+                    n + 1
+                ),
             }
         }
     }
     has_update
 }
 
-fn filter_row(board: &mut Board<SolvingCell>, i: usize, j: usize, n: usize) -> bool {
+/// Removes `n` from the `cells` other than `(i, j)`.
+fn filter_unit<I>(
+    board: &mut Board<SolvingCell>,
+    cells: I,
+    (i, j, n): (usize, usize, usize),
+    pending: &mut Pending,
+) -> bool
+where
+    I: Iterator<Item = (usize, usize)>,
+{
     let mut has_update = false;
-    for j2 in 0..N {
-        if j != j2 {
-            has_update |= board.0[i][j2].remove(n)
+    for (i2, j2) in cells {
+        if (i2, j2) != (i, j) && board.0[i2][j2].remove(n) {
+            pending.mark(i2, j2);
+            has_update = true;
         }
     }
     has_update
 }
 
-fn filter_col(board: &mut Board<SolvingCell>, i: usize, j: usize, n: usize) -> bool {
+/// Removes the value of `(i, j)` from its peers if it has been determined
+/// since the last call. `regions` are the extra regions in addition to the
+/// row, column, and block.
+pub(crate) fn examine_cell(
+    board: &mut Board<SolvingCell>,
+    i: usize,
+    j: usize,
+    regions: &[Region],
+) -> bool {
+    examine_pending_cell(board, i, j, regions, false, &mut Pending::empty())
+}
+
+/// Like [`examine_cell`], and marks the peers that lose a candidate. If
+/// `anti_knight` is true, the cells a knight's move away are also peers.
+fn examine_pending_cell(
+    board: &mut Board<SolvingCell>,
+    i: usize,
+    j: usize,
+    regions: &[Region],
+    anti_knight: bool,
+    pending: &mut Pending,
+) -> bool {
+    if !board.0[i][j].has_update() {
+        return false;
+    }
+
+    board.0[i][j].acknowledge();
+    match board.0[i][j].get_unique() {
+        None => false,
+        Some(n) => {
+            #[cfg(feature = "log")]
+            log::trace!("placed: r{}c{} = {}", i + 1, j + 1, n + 1);
+            let placed = (i, j, n);
+            let mut has_update = filter_unit(board, Unit::Row(i).cells(), placed, pending)
+                | filter_unit(board, Unit::Col(j).cells(), placed, pending)
+                | filter_unit(board, Unit::block_of(i, j).cells(), placed, pending);
+            for region in regions.iter().filter(|r| r.contains(i, j)) {
+                has_update |= filter_unit(board, region.cells(), placed, pending);
+            }
+            if anti_knight {
+                has_update |= filter_unit(board, knight_cells(i, j), placed, pending);
+            }
+            has_update
+        }
+    }
+}
+
+/// Moves of a chess knight.
+const KNIGHT_MOVES: [(isize, isize); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (1, -2),
+    (1, 2),
+    (2, -1),
+    (2, 1),
+];
+
+/// Iterates the cells a knight's move away from `(i, j)`.
+fn knight_cells(i: usize, j: usize) -> impl Iterator<Item = (usize, usize)> {
+    KNIGHT_MOVES.iter().filter_map(move |&(di, dj)| {
+        let i2 = i.checked_add_signed(di).filter(|&i2| i2 < N)?;
+        let j2 = j.checked_add_signed(dj).filter(|&j2| j2 < N)?;
+        Some((i2, j2))
+    })
+}
+
+fn sweep(board: &mut Board<SolvingCell>, options: &Options, pending: &mut Pending) -> bool {
+    let regions = &options.regions;
+    let has_update = match options.sweep_order {
+        SweepOrder::UnitsFirst => {
+            examine_areas(board, regions, pending) | examine_cells(board, options, pending)
+        }
+        SweepOrder::CellsFirst => {
+            examine_cells(board, options, pending) | examine_areas(board, regions, pending)
+        }
+    };
+    let has_update = has_update | compare_cells(board, &options.comparisons, pending);
+    let has_update = has_update | filter_cages(board, &options.cages, pending);
+    has_update || {
+        // Singles alone settle a board that is solved or has a cell without
+        // candidates, which saves the techniques most of the time on easy
+        // puzzles.
+        if options.techniques.iter().next().is_none()
+            || board.0.iter().flatten().all(|c| c.count() == 1)
+            || board.0.iter().flatten().any(|c| c.count() == 0)
+        {
+            return false;
+        }
+        // The techniques do not tell which cells they change.
+        let has_update = options.techniques.apply(board);
+        #[cfg(feature = "log")]
+        if has_update {
+            log::trace!("techniques removed candidates");
+        }
+        pending.mark_updated(board);
+        has_update
+    }
+}
+
+/// Whether every cell of `board` is determined and the values obey all the
+/// constraints of `options`, in which case another sweep would find nothing
+/// to eliminate.
+fn is_settled(board: &Board<SolvingCell>, options: &Options) -> bool {
+    let solution = match board.to_solution() {
+        Some(solution) => solution,
+        None => return false,
+    };
+    let distinct = |cells: &mut dyn Iterator<Item = (usize, usize)>| {
+        let seen = cells.fold(0u64, |seen, (i, j)| seen | 1 << solution.0[i][j]);
+        seen.count_ones() as usize == N
+    };
+    options.units().all(|unit| distinct(&mut unit.cells()))
+        && (!options.anti_knight
+            || (0..N * N).all(|k| {
+                let (i, j) = (k / N, k % N);
+                knight_cells(i, j).all(|(i2, j2)| solution.0[i2][j2] != solution.0[i][j])
+            }))
+        && options.comparisons.iter().all(|c| c.holds(&solution))
+        && options.cages.iter().all(|c| c.holds(&solution))
+}
+
+/// Like [`sweep_all`], but stops as soon as `board` is solved rather than
+/// sweeping once more to find that nothing changes.
+///
+/// The cells determined in the last sweep have not been removed from their
+/// peers yet, so a board is taken as solved only after its values are
+/// checked against the constraints. Otherwise, sweeping goes on to the
+/// fixpoint, which finds the contradiction.
+fn sweep_until_settled(board: &mut Board<SolvingCell>, options: &Options) -> usize {
+    let mut pending = Pending::new(board);
+    let mut sweeps = 1;
+    while sweep(board, options, &mut pending) && !is_settled(board, options) {
+        sweeps += 1;
+    }
+    sweeps
+}
+
+/// Sweeps `board` until nothing changes and returns the number of sweeps.
+fn sweep_all(board: &mut Board<SolvingCell>, options: &Options) -> usize {
+    let mut pending = Pending::new(board);
+    let mut sweeps = 1;
+    while sweep(board, options, &mut pending) {
+        sweeps += 1;
+    }
+    sweeps
+}
+
+/// Finds hidden singles in the pending units.
+fn examine_areas(
+    board: &mut Board<SolvingCell>,
+    regions: &[Region],
+    pending: &mut Pending,
+) -> bool {
     let mut has_update = false;
-    for i2 in 0..N {
-        if i != i2 {
-            has_update |= board.0[i2][j].remove(n)
+    // Units that change from now on are marked again to be searched in the
+    // next sweep.
+    let mut units = std::mem::take(&mut pending.units);
+    while units != 0 {
+        let unit = Pending::unit(units.trailing_zeros() as usize);
+        units &= units - 1;
+        has_update |= examine_area(board, unit.cells(), pending);
+    }
+    if std::mem::take(&mut pending.regions) {
+        for region in regions {
+            has_update |= examine_area(board, region.cells(), pending);
         }
     }
     has_update
 }
 
-fn filter_block(board: &mut Board<SolvingCell>, i: usize, j: usize, n: usize) -> bool {
-    let top = i / N_BLOCK * N_BLOCK;
-    let bottom = top + N_BLOCK;
-    let left = j / N_BLOCK * N_BLOCK;
-    let right = left + N_BLOCK;
+/// Removes the values of the newly determined cells from their peers until
+/// no cell is pending.
+fn examine_cells(board: &mut Board<SolvingCell>, options: &Options, pending: &mut Pending) -> bool {
+    let (regions, anti_knight) = (&options.regions, options.anti_knight);
     let mut has_update = false;
-    for i2 in top..bottom {
-        if i != i2 {
-            for j2 in left..right {
-                if j != j2 {
-                    has_update |= board.0[i2][j2].remove(n)
-                }
+    while let Some((i, j)) = pending.pop_cell() {
+        has_update |= examine_pending_cell(board, i, j, regions, anti_knight, pending);
+    }
+    has_update
+}
+
+/// Removes the candidates that cannot satisfy the `comparisons`: the greater
+/// cell must exceed the least candidate of the lesser, which must be below
+/// the greatest candidate of the greater.
+fn compare_cells(
+    board: &mut Board<SolvingCell>,
+    comparisons: &[Comparison],
+    pending: &mut Pending,
+) -> bool {
+    let mut has_update = false;
+    for comparison in comparisons {
+        let ((i1, j1), (i2, j2)) = comparison.greater_and_lesser();
+        let (min, max) = match (board.0[i2][j2].iter().next(), board.0[i1][j1].iter().last()) {
+            (Some(min), Some(max)) => (min, max),
+            // A cell without candidates is a contradiction found elsewhere.
+            _ => continue,
+        };
+        for n in 0..=min {
+            if board.0[i1][j1].remove(n) {
+                pending.mark(i1, j1);
+                has_update = true;
+            }
+        }
+        for n in max..N {
+            if board.0[i2][j2].remove(n) {
+                pending.mark(i2, j2);
+                has_update = true;
             }
         }
     }
     has_update
 }
 
-fn examine_cell(board: &mut Board<SolvingCell>, i: usize, j: usize) -> bool {
-    if !board.0[i][j].has_update() {
-        return false;
+/// Removes the candidates that no assignment of distinct values adding up to
+/// the sum of the cage gives, for each of the `cages`.
+fn filter_cages(board: &mut Board<SolvingCell>, cages: &[Cage], pending: &mut Pending) -> bool {
+    let mut has_update = false;
+    let mut visited = HashMap::new();
+    for cage in cages {
+        // A cage of more cells than values has no assignment, so nothing is
+        // supported.
+        let mut supported = [0; N];
+        if cage.cells.len() <= N {
+            visited.clear();
+            assign_cage(
+                board,
+                &cage.cells,
+                0,
+                cage.sum,
+                &mut supported,
+                &mut visited,
+            );
+        }
+        for (&(i, j), &values) in cage.cells.iter().zip(&supported) {
+            let cell = board.0[i][j];
+            for n in cell.iter() {
+                if values & 1 << n == 0 && board.0[i][j].remove(n) {
+                    pending.mark(i, j);
+                    has_update = true;
+                }
+            }
+        }
     }
+    has_update
+}
 
-    board.0[i][j].acknowledge();
-    match board.0[i][j].get_unique() {
-        None => false,
-        Some(n) => {
-            filter_row(board, i, j, n) | filter_col(board, i, j, n) | filter_block(board, i, j, n)
+/// Whether the cells of a cage after those given the values in `used` can be
+/// given distinct candidates adding up to `remaining`, as 1-based values.
+///
+/// Every candidate that is part of such an assignment is added to the cell's
+/// `supported` bits. The cells are assigned in order, so `used` tells how many
+/// have been, and the rest of the search depends on `used` only, which is
+/// what `visited` remembers.
+fn assign_cage(
+    board: &Board<SolvingCell>,
+    cells: &[(usize, usize)],
+    used: u32,
+    remaining: usize,
+    supported: &mut [u32],
+    visited: &mut HashMap<u32, bool>,
+) -> bool {
+    let k = used.count_ones() as usize;
+    if k == cells.len() {
+        return remaining == 0;
+    }
+    if let Some(&found) = visited.get(&used) {
+        return found;
+    }
+    let (i, j) = cells[k];
+    let mut found = false;
+    for n in board.0[i][j].iter() {
+        if n + 1 > remaining {
+            break;
+        }
+        if used & 1 << n == 0
+            && assign_cage(
+                board,
+                cells,
+                used | 1 << n,
+                remaining - n - 1,
+                supported,
+                visited,
+            )
+        {
+            supported[k] |= 1 << n;
+            found = true;
         }
     }
+    visited.insert(used, found);
+    found
 }
 
-fn sweep(board: &mut Board<SolvingCell>) -> bool {
-    let mut has_update = false;
+/// Order of the passes in a sweep of propagation.
+///
+/// The order does not change the solutions found, but changes how many
+/// sweeps propagation takes, as reported in [`Stats::sweeps`]. Which order
+/// takes fewer depends on the puzzle.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum SweepOrder {
+    /// Look for hidden singles in the rows, columns, and blocks, and then
+    /// remove the values of determined cells from their peers.
+    #[default]
+    UnitsFirst,
+    /// Remove the values of determined cells from their peers, and then look
+    /// for hidden singles among the candidates left.
+    CellsFirst,
+}
 
-    for i in 0..N {
-        has_update |= examine_area(board, row_iter(i));
+/// Heuristic for choosing the cell to guess when propagation gets stuck.
+///
+/// Except for `RowMajor`, a cell with the fewest candidates is chosen; the
+/// strategies differ in how ties are broken.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Strategy {
+    /// Choose the first tied cell in row-major order.
+    FewestCandidates,
+    /// Choose the tied cell whose row, column, and block have the fewest
+    /// other unsolved cells, so that the guess is most likely to trigger
+    /// further deductions.
+    #[default]
+    MostConstrainedUnits,
+    /// Choose the first unsolved cell in row-major order, however many
+    /// candidates it has.
+    ///
+    /// Since candidates are assumed in ascending order, solutions are found
+    /// in ascending order. This usually needs many more guesses than the
+    /// other strategies.
+    RowMajor,
+}
+
+/// Options that control how the solver searches.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Options {
+    /// Heuristic for choosing the cell to guess.
+    pub strategy: Strategy,
+    /// Techniques used in addition to singles.
+    pub techniques: Techniques,
+    /// Maximum nesting of case analysis. If a guess deeper than this is
+    /// needed, the search stops and [`Stats::depth_exceeded`] is set.
+    /// `None` means no limit.
+    pub max_depth: Option<usize>,
+    /// Regions that must each contain every value once in addition to the
+    /// rows, columns, and blocks, as in variants like Windoku. Only the
+    /// singles take them into account. The other techniques ignore them, and
+    /// the unique rectangle is unsound with them, as it assumes the standard
+    /// rules.
+    pub regions: Vec<Region>,
+    /// Order of the passes in each sweep of propagation.
+    pub sweep_order: SweepOrder,
+    /// Inequalities between cells that the solutions must satisfy, as in
+    /// comparison Sudoku. Like the regions, they are ignored by the
+    /// techniques.
+    pub comparisons: Vec<Comparison>,
+    /// Cages whose values must be distinct and add up to their sums, as in
+    /// killer Sudoku. Like the regions, they are ignored by the techniques.
+    pub cages: Vec<Cage>,
+    /// Solution whose values are assumed first in case analysis, if any.
+    ///
+    /// This does not change the solutions found, only their order. If the
+    /// template is a solution, it is found without backtracking from a wrong
+    /// guess.
+    pub template: Option<Board<usize>>,
+    /// Whether two cells a chess knight's move apart must have different
+    /// values, as in anti-knight Sudoku. Like the regions, this is ignored by
+    /// the techniques.
+    pub anti_knight: bool,
+}
+
+impl Options {
+    /// Iterates the rows, columns, and blocks as by [`all_units`], followed by
+    /// the extra [`regions`](Options::regions), as the regions whose cells must
+    /// each contain every value once under these options.
+    pub fn units(&self) -> impl Iterator<Item = Region> + '_ {
+        all_units()
+            .map(Region::from)
+            .chain(self.regions.iter().copied())
     }
-    for j in 0..N {
-        has_update |= examine_area(board, col_iter(j));
+}
+
+/// Counters of the work done by the solver.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    /// Number of values assumed in case analysis.
+    pub guesses: usize,
+    /// Deepest nesting of case analysis, which is 0 if no guess was needed.
+    pub max_depth: usize,
+    /// Number of calls to `sweep`, including the last one, which found nothing
+    /// unless it solved the board.
+    pub sweeps: usize,
+    /// Whether the search was cut off by [`Options::max_depth`], in which case
+    /// some solutions may not have been found.
+    pub depth_exceeded: bool,
+}
+
+/// State shared by the whole search.
+struct Context<'a> {
+    options: &'a Options,
+    stats: Stats,
+    /// Tree of the guesses so far, if it is being recorded.
+    tree: Option<SearchTree>,
+    /// Index of the node of the current board in `tree`.
+    node: usize,
+    /// Board of the contradiction with the most determined cells so far.
+    contradiction: Option<Board<SolvingCell>>,
+    /// Generator that shuffles the guesses, if they are to be random.
+    rng: Option<&'a mut Rng>,
+    /// Number of nodes of the search tree, the root and a node for each
+    /// guess, after which the search stops.
+    node_limit: Option<u64>,
+}
+
+impl Context<'_> {
+    fn set_outcome(&mut self, outcome: Outcome) {
+        if let Some(tree) = &mut self.tree {
+            tree.nodes[self.node].outcome = outcome;
+        }
     }
-    for i in 0..N_BLOCK {
-        for j in 0..N_BLOCK {
-            has_update |= examine_area(board, block_iter(i * N_BLOCK, j * N_BLOCK));
+
+    /// Keeps `board` as the contradiction if it is propagated at least as far
+    /// as the one kept.
+    fn record_contradiction(&mut self, board: &Board<SolvingCell>) {
+        let determined =
+            |b: &Board<SolvingCell>| b.0.iter().flatten().filter(|c| c.count() == 1).count();
+        if self
+            .contradiction
+            .as_ref()
+            .is_none_or(|kept| determined(kept) <= determined(board))
+        {
+            self.contradiction = Some(*board);
         }
     }
+}
 
-    for i in 0..N {
-        for j in 0..N {
-            has_update |= examine_cell(board, i, j);
+/// Number of unsolved cells other than `(i, j)` in the row, column, and block
+/// of `(i, j)`.
+fn unsolved_peers(board: &Board<SolvingCell>, i: usize, j: usize) -> usize {
+    row_iter(i)
+        .chain(col_iter(j))
+        .chain(Unit::block_of(i, j).cells())
+        .filter(|&(i2, j2)| (i2, j2) != (i, j) && board.0[i2][j2].count() > 1)
+        .count()
+}
+
+/// Chooses the cell to guess in an unsolved `board`, returning its index in
+/// row-major order. A cell without candidates is chosen if there is one.
+/// A cell with exactly one candidate is never chosen.
+///
+/// # Panics
+///
+/// If every cell of `board` has exactly one candidate.
+pub(crate) fn choose_cell(board: &Board<SolvingCell>, strategy: Strategy) -> usize {
+    let count = |k: usize| board.0[k / N][k % N].count();
+    let unsolved = || (0..(N * N)).filter(|&k| count(k) != 1);
+    // Find a cell with least possibilities.
+    let k = unsolved()
+        .min_by_key(|&k| count(k))
+        .expect("a solved board has no cell to guess");
+    if count(k) == 0 {
+        return k;
+    }
+    match strategy {
+        Strategy::FewestCandidates => k,
+        Strategy::MostConstrainedUnits => {
+            let c = count(k);
+            unsolved()
+                .filter(|&k| count(k) == c)
+                .min_by_key(|&k| unsolved_peers(board, k / N, k % N))
+                .unwrap()
         }
+        Strategy::RowMajor => unsolved().next().unwrap(),
     }
+}
 
-    has_update
+/// Whether removing the value of `(i, j)` from its peers leaves some cell
+/// without candidates. `board` is not changed.
+fn empties_peer(board: &Board<SolvingCell>, i: usize, j: usize, regions: &[Region]) -> bool {
+    let mut probe = *board;
+    examine_cell(&mut probe, i, j, regions);
+    probe.0.iter().flatten().any(|c| c.count() == 0)
 }
 
-fn case_analysis<F>(board: Board<SolvingCell>, f: &mut F)
+fn case_analysis<F>(
+    board: Board<SolvingCell>,
+    depth: usize,
+    ctx: &mut Context<'_>,
+    f: &mut F,
+) -> ControlFlow<()>
 where
-    F: FnMut(Board<usize>),
+    F: FnMut(Board<usize>) -> ControlFlow<()>,
 {
-    // Find a cell with least possibilities.
-    let k = (0..(N * N))
-        .min_by_key(|k| {
-            let c = board.0[k / N][k % N].count();
-            if c == 1 {
-                N + 1
-            } else {
-                c
-            }
-        })
-        .unwrap();
+    // Case analysis starts where propagation has reached a fixpoint, which
+    // leaves no cell with its update flag set.
+    invariant!(
+        board.0.iter().flatten().all(|c| !c.has_update()),
+        "case analysis before propagation finished"
+    );
+    let mut k = choose_cell(&board, ctx.options.strategy);
+    if let Some(rng) = &mut ctx.rng {
+        // Any cell with as few candidates is as good a guess.
+        let c = board.0[k / N][k % N].count();
+        let ties = || (0..N * N).filter(|&k| board.0[k / N][k % N].count() == c);
+        k = ties().nth(rng.below(ties().count())).unwrap();
+    }
+    if board.0[k / N][k % N].count() == 0 {
+        #[cfg(feature = "log")]
+        log::debug!(
+            "contradiction at depth {}: no candidate for r{}c{}",
+            depth,
+            k / N + 1,
+            k % N + 1
+        );
+        ctx.set_outcome(Outcome::Contradiction);
+        ctx.record_contradiction(&board);
+        return ControlFlow::Continue(());
+    }
 
     // Assume each possibility and solve again.
-    for n in board.0[k / N][k % N].iter() {
+    let depth = depth + 1;
+    if ctx.options.max_depth.is_some_and(|max| depth > max) {
+        ctx.stats.depth_exceeded = true;
+        ctx.set_outcome(Outcome::DepthExceeded);
+        return ControlFlow::Break(());
+    }
+    ctx.stats.max_depth = ctx.stats.max_depth.max(depth);
+    let parent = ctx.node;
+    let cell = board.0[k / N][k % N];
+    let preferred = ctx
+        .options
+        .template
+        .map(|t| t.0[k / N][k % N])
+        .filter(|&n| cell.can_be(n));
+    let others = cell.iter().filter(|&n| Some(n) != preferred);
+    let mut order = [0; N];
+    let mut len = 0;
+    for n in preferred.into_iter().chain(others) {
+        order[len] = n;
+        len += 1;
+    }
+    if let Some(rng) = &mut ctx.rng {
+        // The preferred value stays first.
+        let skip = preferred.iter().count();
+        rng.shuffle(&mut order[skip..len]);
+    }
+    for &n in &order[..len] {
         let mut board2 = board;
         board2.0[k / N][k % N] = SolvingCell::new(Some(n));
-        assert_ne!(board, board2);
-        solve(board2, f);
+        // The guessed cell had other candidates, so the guess makes progress
+        // and the search terminates.
+        invariant!(board != board2, "guess did not change the board");
+        #[cfg(feature = "log")]
+        log::debug!(
+            "guess at depth {}: r{}c{} = {}",
+            depth,
+            k / N + 1,
+            k % N + 1,
+            n + 1
+        );
+        if ctx
+            .node_limit
+            .is_some_and(|limit| ctx.stats.guesses as u64 + 1 >= limit)
+        {
+            return ControlFlow::Break(());
+        }
+        ctx.stats.guesses += 1;
+        if let Some(tree) = &mut ctx.tree {
+            tree.nodes.push(Node {
+                parent: Some(parent),
+                guess: Some((k / N, k % N, n)),
+                outcome: Outcome::Branch,
+            });
+            ctx.node = tree.nodes.len() - 1;
+        }
+        // Checking the guess forward is no use: a peer could only be left
+        // without candidates if it were already determined as `n`, but then
+        // `n` would have been removed from the guessed cell.
+        invariant!(
+            !empties_peer(&board2, k / N, k % N, &ctx.options.regions),
+            "guess left a peer without candidates"
+        );
+        let flow = solve(board2, depth, ctx, f);
+        ctx.node = parent;
+        flow?;
     }
+    ControlFlow::Continue(())
 }
 
-fn solve<F>(mut board: Board<SolvingCell>, f: &mut F)
+fn solve<F>(
+    mut board: Board<SolvingCell>,
+    depth: usize,
+    ctx: &mut Context<'_>,
+    f: &mut F,
+) -> ControlFlow<()>
 where
-    F: FnMut(Board<usize>),
+    F: FnMut(Board<usize>) -> ControlFlow<()>,
 {
-    while sweep(&mut board) {}
+    ctx.stats.sweeps += sweep_until_settled(&mut board, ctx.options);
 
     if let Some(solution) = board.to_solution() {
-        f(solution);
-        return;
+        // Every value placed has been removed from its peers, so a wrong
+        // solution means a technique removed a candidate it should not have.
+        invariant!(
+            solution.is_valid_complete(),
+            "propagation yielded an invalid solution:\n{}",
+            solution
+        );
+        #[cfg(feature = "log")]
+        log::debug!("solution at depth {}", depth);
+        ctx.set_outcome(Outcome::Solution);
+        return f(solution);
     }
 
-    case_analysis(board, f);
+    case_analysis(board, depth, ctx, f)
+}
+
+pub(crate) fn to_solving_board(problem: &Board<Option<usize>>) -> Board<SolvingCell> {
+    problem.map(|&v| SolvingCell::new(v))
 }
 
-pub fn for_each_solution<F>(problem: &Board<Option<usize>>, mut f: F)
+/// Solves `problem`, calling `f` for each solution until `f` breaks.
+fn search<F>(problem: &Board<Option<usize>>, options: &Options, f: F) -> (ControlFlow<()>, Stats)
+where
+    F: FnMut(Board<usize>) -> ControlFlow<()>,
+{
+    search_board(to_solving_board(problem), options, f)
+}
+
+/// Like [`search`], but starts from candidates.
+fn search_board<F>(
+    board: Board<SolvingCell>,
+    options: &Options,
+    mut f: F,
+) -> (ControlFlow<()>, Stats)
+where
+    F: FnMut(Board<usize>) -> ControlFlow<()>,
+{
+    let mut ctx = Context {
+        options,
+        stats: Stats::default(),
+        tree: None,
+        node: 0,
+        contradiction: None,
+        rng: None,
+        node_limit: None,
+    };
+    let flow = solve(board, 0, &mut ctx, &mut f);
+    (flow, ctx.stats)
+}
+
+/// Searches all solutions of `problem` as configured by `options`, and
+/// returns the tree of the guesses made.
+///
+/// The tree can get huge for a puzzle with many solutions, so consider
+/// setting [`Options::max_depth`] for such puzzles.
+pub fn search_tree(problem: &Board<Option<usize>>, options: &Options) -> SearchTree {
+    let mut ctx = Context {
+        options,
+        stats: Stats::default(),
+        tree: Some(SearchTree::new()),
+        node: 0,
+        contradiction: None,
+        rng: None,
+        node_limit: None,
+    };
+    let _ = solve(to_solving_board(problem), 0, &mut ctx, &mut |_| {
+        ControlFlow::Continue(())
+    });
+    ctx.tree.unwrap()
+}
+
+pub fn for_each_solution<F>(problem: &Board<Option<usize>>, f: F)
 where
     F: FnMut(Board<usize>),
 {
-    // Convert to Board<SolvingCell>
-    let mut solving_board = Board([[SolvingCell::new(None); N]; N]);
-    for i in 0..N {
-        for j in 0..N {
-            solving_board.0[i][j] = SolvingCell::new(problem.0[i][j]);
+    for_each_solution_with(problem, &Options::default(), f)
+}
+
+/// Like [`for_each_solution`], but searches as configured by `options`.
+pub fn for_each_solution_with<F>(problem: &Board<Option<usize>>, options: &Options, f: F)
+where
+    F: FnMut(Board<usize>),
+{
+    for_each_solution_with_stats(problem, options, f);
+}
+
+/// Like [`for_each_solution_with`], and returns how much work the search
+/// took.
+pub fn for_each_solution_with_stats<F>(
+    problem: &Board<Option<usize>>,
+    options: &Options,
+    mut f: F,
+) -> Stats
+where
+    F: FnMut(Board<usize>),
+{
+    let (_, stats) = search(problem, options, |b| {
+        f(b);
+        ControlFlow::Continue(())
+    });
+    stats
+}
+
+/// Solves a Sukaku puzzle, calling `f` for each solution.
+///
+/// A Sukaku has no givens; instead, each cell of `candidates` lists the values
+/// it may take, and a solution takes one of them in every cell that obeys the
+/// usual rules. A cell with no candidates makes the puzzle unsolvable. The
+/// update flags of `candidates` do not matter, as every cell is examined.
+pub fn for_each_sukaku_solution<F>(candidates: &Board<SolvingCell>, mut f: F)
+where
+    F: FnMut(Board<usize>),
+{
+    let board = candidates.map(|cell| SolvingCell::from_candidates(cell.iter()));
+    let _ = search_board(board, &Options::default(), |b| {
+        f(b);
+        ControlFlow::Continue(())
+    });
+}
+
+/// Like [`for_each_solution`], but guesses the values of `template` first, so
+/// that the solution matching it, if any, is found before the others.
+///
+/// This is handy to check that a hand-made puzzle leads to the intended
+/// answer. To stop at the first solution, use [`try_for_each_solution`] with
+/// [`Options::template`].
+pub fn solve_toward<F>(problem: &Board<Option<usize>>, template: &Board<usize>, f: F)
+where
+    F: FnMut(Board<usize>),
+{
+    let options = Options {
+        template: Some(*template),
+        ..Options::default()
+    };
+    for_each_solution_with(problem, &options, f);
+}
+
+/// Like [`for_each_solution`], but calls `f` only for the solutions that
+/// satisfy `predicate`.
+///
+/// This is a hook for constraints that the solver does not know about. The
+/// predicate filters the solutions found; it does not prune the search, so
+/// the search takes as long as without it.
+pub fn for_each_solution_where<P, F>(problem: &Board<Option<usize>>, predicate: P, mut f: F)
+where
+    P: Fn(&Board<usize>) -> bool,
+    F: FnMut(Board<usize>),
+{
+    for_each_solution(problem, |b| {
+        if predicate(&b) {
+            f(b)
+        }
+    })
+}
+
+/// Like [`for_each_solution_with`], but stops the search as soon as `f`
+/// returns [`ControlFlow::Break`].
+///
+/// The result is `Break` if the search was stopped by `f` or by
+/// [`Options::max_depth`], and `Continue` if all solutions have been found.
+///
+/// ```
+/// # use std::ops::ControlFlow;
+/// # use sudoku_solver_in_rust::board::Board;
+/// # use sudoku_solver_in_rust::solver::{try_for_each_solution, Options};
+/// # // Guessing on the empty 25x25 board nests too deep for the main thread.
+/// # if sudoku_solver_in_rust::board::N > 16 {
+/// #     return;
+/// # }
+/// let mut solutions = Vec::new();
+/// let flow = try_for_each_solution(&Board::empty(), &Options::default(), |b| {
+///     solutions.push(b);
+///     if solutions.len() < 5 {
+///         ControlFlow::Continue(())
+///     } else {
+///         ControlFlow::Break(())
+///     }
+/// });
+/// assert_eq!(flow, ControlFlow::Break(()));
+/// assert_eq!(solutions.len(), 5);
+/// ```
+pub fn try_for_each_solution<F>(
+    problem: &Board<Option<usize>>,
+    options: &Options,
+    f: F,
+) -> ControlFlow<()>
+where
+    F: FnMut(Board<usize>) -> ControlFlow<()>,
+{
+    search(problem, options, f).0
+}
+
+/// Result of [`solve_logically`].
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LogicalOutcome {
+    /// Every cell has been determined.
+    Solved(Board<usize>),
+    /// No more deduction can be made, but some cells have more than one
+    /// candidate left.
+    Stuck(Board<SolvingCell>),
+    /// Some cell has no candidate left, so the puzzle has no solution.
+    Contradiction(Board<SolvingCell>),
+}
+
+/// Solves `problem` by propagation and `techniques` only, without guessing.
+pub fn solve_logically(problem: &Board<Option<usize>>, techniques: &Techniques) -> LogicalOutcome {
+    let options = Options {
+        techniques: *techniques,
+        ..Options::default()
+    };
+    let mut board = to_solving_board(problem);
+    sweep_all(&mut board, &options);
+
+    if let Some(solution) = board.to_solution() {
+        LogicalOutcome::Solved(solution)
+    } else if board.0.iter().flatten().any(|c| c.count() == 0) {
+        LogicalOutcome::Contradiction(board)
+    } else {
+        LogicalOutcome::Stuck(board)
+    }
+}
+
+/// Number of cells that [`solve_logically`] leaves undetermined with the
+/// default techniques.
+///
+/// Zero means the puzzle can be solved without guessing. Cells left without
+/// any candidate by a contradiction are counted as undetermined.
+pub fn logical_progress(problem: &Board<Option<usize>>) -> usize {
+    match solve_logically(problem, &Techniques::default()) {
+        LogicalOutcome::Solved(_) => 0,
+        LogicalOutcome::Stuck(board) | LogicalOutcome::Contradiction(board) => board
+            .0
+            .iter()
+            .flatten()
+            .filter(|c| c.get_unique().is_none())
+            .count(),
+    }
+}
+
+/// Distribution of candidate counts after propagation.
+///
+/// Index `k` of the result is the number of cells that have exactly `k`
+/// candidates left once [`propagate`] stalls. The counts sum up to `N * N`.
+pub fn candidate_histogram(problem: &Board<Option<usize>>) -> [usize; N + 1] {
+    let mut histogram = [0; N + 1];
+    for cell in propagate(problem).0.iter().flatten() {
+        histogram[cell.count()] += 1;
+    }
+    histogram
+}
+
+/// Returns the cell that the solver guesses first in solving `problem` with
+/// the default options, and its candidates, all 0-based.
+///
+/// The result is `None` if propagation solves the puzzle or finds a
+/// contradiction, so no guess is needed.
+pub fn branch_point(problem: &Board<Option<usize>>) -> Option<(usize, usize, Vec<usize>)> {
+    let options = Options::default();
+    let board = match solve_logically(problem, &options.techniques) {
+        LogicalOutcome::Stuck(board) => board,
+        LogicalOutcome::Solved(_) | LogicalOutcome::Contradiction(_) => return None,
+    };
+    let k = choose_cell(&board, options.strategy);
+    let (i, j) = (k / N, k % N);
+    Some((i, j, board.0[i][j].iter().collect()))
+}
+
+/// Suggests a guess for a `board` that logic cannot solve further, as 0-based
+/// row, column, and value.
+///
+/// The cell is the one the solver would guess, and of its candidates, the one
+/// that determines the most cells by propagation is preferred, the smallest on
+/// a tie. Candidates that propagation refutes are never suggested. The result
+/// is `None` if `board` is solved or some cell has no candidates, or if every
+/// candidate of the cell is refuted.
+///
+/// No logic backs the choice, so the suggested value may be wrong even though
+/// it does not lead to a contradiction right away.
+pub fn best_guess(board: &Board<SolvingCell>) -> Option<(usize, usize, usize)> {
+    let cells = || board.0.iter().flatten();
+    if cells().all(|c| c.count() == 1) || cells().any(|c| c.count() == 0) {
+        return None;
+    }
+    let k = choose_cell(board, Strategy::default());
+    let (i, j) = (k / N, k % N);
+    let mut best = None;
+    let mut best_score = 0;
+    for n in board.0[i][j].iter() {
+        let mut after = *board;
+        add_clue(&mut after, i, j, n);
+        if after.0.iter().flatten().any(|c| c.count() == 0) {
+            continue;
+        }
+        let score = after.0.iter().flatten().filter(|c| c.count() == 1).count();
+        if best.is_none() || score > best_score {
+            best = Some((i, j, n));
+            best_score = score;
+        }
+    }
+    best
+}
+
+/// Returns the first solution of `problem` found, if any.
+pub fn solve_first(problem: &Board<Option<usize>>) -> Option<Board<usize>> {
+    let mut solution = None;
+    let _ = search(problem, &Options::default(), |b| {
+        solution = Some(b);
+        ControlFlow::Break(())
+    });
+    solution
+}
+
+/// Like [`solve_first`], but guesses in an order shuffled by `rng`.
+///
+/// Ties between the cells to guess and the order of the candidates of the
+/// guessed cell are decided at random, so different seeds find different
+/// solutions of a puzzle with many, such as the empty board. The same seed
+/// always finds the same solution.
+pub fn solve_first_random(problem: &Board<Option<usize>>, rng: &mut Rng) -> Option<Board<usize>> {
+    let options = Options::default();
+    let mut ctx = Context {
+        options: &options,
+        stats: Stats::default(),
+        tree: None,
+        node: 0,
+        contradiction: None,
+        rng: Some(rng),
+        node_limit: None,
+    };
+    let mut solution = None;
+    let _ = solve(to_solving_board(problem), 0, &mut ctx, &mut |b| {
+        solution = Some(b);
+        ControlFlow::Break(())
+    });
+    solution
+}
+
+/// Buffer reused in solving many puzzles one after another, so that solving
+/// the next puzzle does not allocate again.
+///
+/// The branches not tried yet are kept in a vector owned by the scratch
+/// rather than in the frames of a recursion. The vector is cleared, not
+/// freed, between puzzles, so once it has grown to the deepest search so far,
+/// solving makes no allocation.
+///
+/// ```
+/// # use sudoku_solver_in_rust::board::Board;
+/// # use sudoku_solver_in_rust::solver::{solve_first, Scratch};
+/// let mut scratch = Scratch::new();
+/// let problems = [Board::empty(), Board::empty().with_clue(0, 0, 1)];
+/// for problem in &problems {
+///     assert_eq!(scratch.solve_first(problem), solve_first(problem));
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Scratch {
+    /// Boards of the untried branches, the next one to try last.
+    branches: Vec<Board<SolvingCell>>,
+}
+
+impl Scratch {
+    /// Creates an empty scratch, which allocates when first used.
+    pub fn new() -> Scratch {
+        Scratch::default()
+    }
+
+    /// Returns the same solution as [`solve_first`], reusing the buffer of
+    /// `self`.
+    pub fn solve_first(&mut self, problem: &Board<Option<usize>>) -> Option<Board<usize>> {
+        let options = Options::default();
+        self.branches.clear();
+        self.branches.push(to_solving_board(problem));
+        while let Some(mut board) = self.branches.pop() {
+            sweep_until_settled(&mut board, &options);
+            if let Some(solution) = board.to_solution() {
+                invariant!(
+                    solution.is_valid_complete(),
+                    "propagation yielded an invalid solution:\n{}",
+                    solution
+                );
+                self.branches.clear();
+                return Some(solution);
+            }
+            // The values are pushed in descending order so that they are tried
+            // in ascending order, as in case analysis. A cell without a
+            // candidate pushes nothing, which backtracks.
+            let k = choose_cell(&board, options.strategy);
+            let cell = board.0[k / N][k % N];
+            for n in (0..N).rev().filter(|&n| cell.can_be(n)) {
+                let mut branch = board;
+                branch.0[k / N][k % N] = SolvingCell::new(Some(n));
+                self.branches.push(branch);
+            }
+        }
+        None
+    }
+}
+
+/// Block shared by two boards, as in Samurai Sudoku, where the corner blocks
+/// of the middle grid are the inner corner blocks of the others.
+///
+/// Each block is given by its top-left cell, as passed to [`block_iter`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Overlap {
+    /// Top-left cell of the shared block in the first board.
+    pub first: (usize, usize),
+    /// Top-left cell of the shared block in the second board.
+    pub second: (usize, usize),
+}
+
+impl Overlap {
+    /// Iterates the pairs of cells that are the same cell, the one in the
+    /// first board and the one in the second.
+    ///
+    /// # Panics
+    ///
+    /// If a block is not given by its top-left cell.
+    pub fn cells(self) -> impl Iterator<Item = ((usize, usize), (usize, usize))> {
+        block_iter(self.first.0, self.first.1).zip(block_iter(self.second.0, self.second.1))
+    }
+}
+
+/// Removes from each shared cell the candidates that the same cell of the
+/// other board does not have. Returns true if any candidate was removed.
+fn synchronize(
+    first: &mut Board<SolvingCell>,
+    second: &mut Board<SolvingCell>,
+    overlap: Overlap,
+) -> bool {
+    let mut changed = false;
+    for ((i1, j1), (i2, j2)) in overlap.cells() {
+        let common = first.0[i1][j1].intersect(&second.0[i2][j2]);
+        for (board, i, j) in [(&mut *first, i1, j1), (&mut *second, i2, j2)] {
+            for n in board.0[i][j].difference(&common).iter() {
+                changed |= board.0[i][j].remove(n);
+            }
+        }
+    }
+    changed
+}
+
+fn solve_overlapping_boards(
+    mut first: Board<SolvingCell>,
+    mut second: Board<SolvingCell>,
+    overlap: Overlap,
+) -> Option<(Board<usize>, Board<usize>)> {
+    let options = Options::default();
+    loop {
+        sweep_all(&mut first, &options);
+        sweep_all(&mut second, &options);
+        if !synchronize(&mut first, &mut second, overlap) {
+            break;
+        }
+    }
+    let cells = || first.0.iter().flatten().chain(second.0.iter().flatten());
+    if cells().any(|c| c.count() == 0) {
+        return None;
+    }
+    // At the fixpoint, every determined value has been removed from its
+    // peers, so determined boards are solutions.
+    let (solution1, solution2) = (first.to_solution(), second.to_solution());
+    if let (Some(solution1), Some(solution2)) = (solution1, solution2) {
+        return Some((solution1, solution2));
+    }
+
+    let guess_first = solution1.is_none();
+    let unsolved = if guess_first { &first } else { &second };
+    let k = choose_cell(unsolved, Strategy::default());
+    for n in unsolved.0[k / N][k % N].iter() {
+        let (mut first2, mut second2) = (first, second);
+        let guessed = if guess_first {
+            &mut first2
+        } else {
+            &mut second2
+        };
+        guessed.0[k / N][k % N] = SolvingCell::new(Some(n));
+        let solutions = solve_overlapping_boards(first2, second2, overlap);
+        if solutions.is_some() {
+            return solutions;
+        }
+    }
+    None
+}
+
+/// Solves two puzzles that share the `overlap` block and returns the first
+/// pair of solutions found, if any, which agree on the shared cells.
+///
+/// The candidates of the shared cells are kept the same in both boards
+/// throughout propagation, so a value found in one board is used in solving
+/// the other. This is the basis of solving Samurai Sudoku and other variants
+/// of overlapping grids.
+///
+/// # Panics
+///
+/// If a block of `overlap` is not given by its top-left cell.
+pub fn solve_overlapping(
+    first: &Board<Option<usize>>,
+    second: &Board<Option<usize>>,
+    overlap: Overlap,
+) -> Option<(Board<usize>, Board<usize>)> {
+    solve_overlapping_boards(to_solving_board(first), to_solving_board(second), overlap)
+}
+
+/// Returns the first solution of `problem` found, or the board where the
+/// search got furthest before failing if there is no solution.
+///
+/// Of the boards where a contradiction was detected, the result is the one
+/// with the most determined cells, the last one on a tie. Its cell without
+/// candidates shows where the puzzle breaks down.
+#[allow(clippy::result_large_err)]
+pub fn solve_or_contradiction(
+    problem: &Board<Option<usize>>,
+) -> Result<Board<usize>, Board<SolvingCell>> {
+    let options = Options::default();
+    let mut ctx = Context {
+        options: &options,
+        stats: Stats::default(),
+        tree: None,
+        node: 0,
+        contradiction: None,
+        rng: None,
+        node_limit: None,
+    };
+    let mut solution = None;
+    let _ = solve(to_solving_board(problem), 0, &mut ctx, &mut |b| {
+        solution = Some(b);
+        ControlFlow::Break(())
+    });
+    // Without a depth limit, every branch ends in a solution or a
+    // contradiction.
+    solution.ok_or_else(|| ctx.contradiction.unwrap())
+}
+
+/// Result of [`solve_matrix`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MatrixSolution {
+    /// The first solution found.
+    pub solution: Board<usize>,
+    /// The cells that were empty in the matrix, in row-major order.
+    pub inferred: Vec<(usize, usize)>,
+}
+
+/// Solves a puzzle read as numbers, such as by OCR, where 1 to 9 are clues
+/// and any other number, like 0 or -1 for an uncertain read, is an empty
+/// cell.
+///
+/// The result tells the cells that the solver filled in, so that the caller
+/// can cross-check them against the reads of low confidence. Returns `None`
+/// if there is no solution, which may be because a clue was misread.
+pub fn solve_matrix(matrix: &[[i32; N]; N]) -> Option<MatrixSolution> {
+    let problem = Board(matrix.map(|row| {
+        row.map(|v| match v {
+            v if v >= 1 && v as usize <= N => Some(v as usize - 1),
+            _ => None,
+        })
+    }));
+    let solution = solve_first(&problem)?;
+    Some(MatrixSolution {
+        solution,
+        inferred: problem.cells_where(Option::is_none),
+    })
+}
+
+/// Returns the candidates of `problem` after propagation reaches a fixpoint.
+///
+/// The result can be kept by an editor and updated with [`add_clue`] as the
+/// user adds clues. After a clue is removed, it is computed again by this
+/// function.
+pub fn propagate(problem: &Board<Option<usize>>) -> Board<SolvingCell> {
+    let mut board = to_solving_board(problem);
+    sweep_all(&mut board, &Options::default());
+    board
+}
+
+/// Returns the smallest solution of `problem` in the order of [`Board`], if
+/// any.
+///
+/// Unlike [`solve_first`], the result is well-defined for a puzzle with more
+/// than one solution, which makes it suitable as a canonical answer.
+pub fn solve_canonical(problem: &Board<Option<usize>>) -> Option<Board<usize>> {
+    let options = Options {
+        strategy: Strategy::RowMajor,
+        ..Options::default()
+    };
+    let mut solution = None;
+    let _ = search(problem, &options, |b| {
+        solution = Some(b);
+        ControlFlow::Break(())
+    });
+    solution
+}
+
+/// Returns the 0-based row, column, and value of every cell of `board` that
+/// has exactly one candidate, in row-major order, without placing them.
+///
+/// This includes cells that were already determined, such as the givens, so
+/// compare with the puzzle to find only the new ones.
+pub fn naked_singles(board: &Board<SolvingCell>) -> Vec<(usize, usize, usize)> {
+    (0..N)
+        .flat_map(|i| (0..N).map(move |j| (i, j)))
+        .filter_map(|(i, j)| board.0[i][j].get_unique().map(|n| (i, j, n)))
+        .collect()
+}
+
+/// Returns `problem` with the naked singles filled in as clues, repeatedly
+/// until none is left, but without any other deduction.
+///
+/// This gives a player a warm start on a puzzle without solving it. Hidden
+/// singles are left for the player, and so are the naked singles that only
+/// appear after a hidden single is placed.
+pub fn prefill_singles(problem: &Board<Option<usize>>) -> Board<Option<usize>> {
+    let mut board = to_solving_board(problem);
+    let mut has_update = true;
+    while has_update {
+        has_update = false;
+        for (i, j) in (0..N).flat_map(|i| (0..N).map(move |j| (i, j))) {
+            has_update |= examine_cell(&mut board, i, j, &[]);
         }
     }
+    board.map(SolvingCell::get_unique)
+}
+
+/// Places `n` at `(i, j)` of a board returned by [`propagate`] and propagates
+/// the consequences with the default [`Options`], that is, by the singles
+/// under the standard rules alone.
+///
+/// Returns false without changing `board` if `n` is not a candidate of the
+/// cell, since placing it would bring back a value that propagation has
+/// already ruled out.
+///
+/// This is cheap: only the new placement's eliminations and what follows from
+/// them are computed, since everything deduced before remains valid with one
+/// more clue. Removing a clue is not cheap, because any candidate may have
+/// been eliminated on account of it, possibly through a chain of deductions
+/// reaching far from its row, column, and block. The candidates are then
+/// recomputed in full by [`propagate`] on the puzzle without the clue.
+pub fn add_clue(board: &mut Board<SolvingCell>, i: usize, j: usize, n: usize) -> bool {
+    if !board.0[i][j].can_be(n) {
+        return false;
+    }
+    board.0[i][j] = SolvingCell::new(Some(n));
+    sweep_all(board, &Options::default());
+    true
+}
+
+/// Returns the cells that become determined when `n` is placed at `(i, j)` of
+/// `board`, as 0-based row, column, and value in row-major order.
+///
+/// Only propagation is used, without guessing, and `board` is not changed.
+/// The placed cell and the cells that were already determined are not
+/// included. If the placement leads to a contradiction, the cells determined
+/// up to it are returned, which may conflict with each other. If `n` is not a
+/// candidate of the cell, nothing is returned.
+pub fn consequences(
+    board: &Board<SolvingCell>,
+    i: usize,
+    j: usize,
+    n: usize,
+) -> Vec<(usize, usize, usize)> {
+    let mut after = *board;
+    if !add_clue(&mut after, i, j, n) {
+        return Vec::new();
+    }
+    naked_singles(&after)
+        .into_iter()
+        .filter(|&(i2, j2, _)| (i2, j2) != (i, j) && board.0[i2][j2].get_unique().is_none())
+        .collect()
+}
+
+/// Searches for solutions of `problem` in a background thread and returns a
+/// receiver that yields them as they are found.
+///
+/// The search runs at most 16 solutions ahead of the receiver, so no more
+/// than that are buffered however many solutions there are. Dropping the
+/// receiver stops the search at the next solution found.
+pub fn solutions_channel(problem: &Board<Option<usize>>) -> Receiver<Board<usize>> {
+    let (sender, receiver) = sync_channel(16);
+    let problem = *problem;
+    thread::spawn(move || {
+        let _ = search(&problem, &Options::default(), |b| match sender.send(b) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(_) => ControlFlow::Break(()),
+        });
+    });
+    receiver
+}
+
+/// Solves each of `problems` with [`solve_first`] on `threads` threads, and
+/// calls `f` with the results in the order of `problems`.
+///
+/// Each thread reuses one [`Scratch`] for all the problems it solves.
+///
+/// The problems are taken from the iterator only as fast as `f` consumes the
+/// results, with at most `4 * threads` of them read but not yet passed to
+/// `f`, so memory use does not grow with the length of the input even if one
+/// puzzle takes much longer than the ones after it. With one thread, the
+/// problems are solved in turn on the calling thread.
+///
+/// # Panics
+///
+/// If `threads` is zero.
+pub fn solve_first_each<I, F>(problems: I, threads: usize, mut f: F)
+where
+    I: IntoIterator<Item = Board<Option<usize>>>,
+    I::IntoIter: Send,
+    F: FnMut(Option<Board<usize>>),
+{
+    assert_ne!(threads, 0, "no thread to solve puzzles");
+    if threads == 1 {
+        let mut scratch = Scratch::new();
+        problems
+            .into_iter()
+            .for_each(|problem| f(scratch.solve_first(&problem)));
+        return;
+    }
 
-    solve(solving_board, &mut f);
+    // The reader takes a credit for each problem and the writer gives it
+    // back when the result is passed to `f`, which bounds the results that
+    // wait for an earlier one to finish.
+    let window = 4 * threads;
+    let (credit_sender, credit_receiver) = sync_channel(window);
+    for _ in 0..window {
+        credit_sender.send(()).unwrap();
+    }
+    let (job_sender, job_receiver) = sync_channel(window);
+    let job_receiver = Mutex::new(job_receiver);
+    let (result_sender, result_receiver) = sync_channel(window);
+    let problems = problems.into_iter();
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            for job in problems.enumerate() {
+                if credit_receiver.recv().is_err() || job_sender.send(job).is_err() {
+                    break;
+                }
+            }
+        });
+        for _ in 0..threads {
+            let result_sender = result_sender.clone();
+            let job_receiver = &job_receiver;
+            scope.spawn(move || {
+                // The lock is released before solving.
+                let next_job = || job_receiver.lock().unwrap().recv().ok();
+                let mut scratch = Scratch::new();
+                while let Some((index, problem)) = next_job() {
+                    if result_sender
+                        .send((index, scratch.solve_first(&problem)))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(result_sender);
+
+        let mut finished = BTreeMap::new();
+        let mut next = 0;
+        for (index, solution) in result_receiver {
+            finished.insert(index, solution);
+            while let Some(solution) = finished.remove(&next) {
+                f(solution);
+                next += 1;
+                let _ = credit_sender.send(());
+            }
+        }
+    });
+}
+
+/// Returns the number of nodes in the tree of the guesses made in searching
+/// all solutions of `problem`, or `node_limit` if there are more.
+///
+/// The root counts as a node, so the result is 1 for a puzzle solved without
+/// guessing. The search stops once `node_limit` nodes have been visited, so
+/// this is a cheap measure of how hard a puzzle is to solve by brute force,
+/// unlike [`solving_path_length`](crate::steps::solving_path_length), which
+/// rates the logic needed. The count depends on the cell chosen for each
+/// guess, so it measures the effort of this solver with the default
+/// [`Strategy`] rather than of the puzzle alone.
+pub fn search_effort(problem: &Board<Option<usize>>, node_limit: u64) -> u64 {
+    if node_limit == 0 {
+        return 0;
+    }
+    let options = Options::default();
+    let mut ctx = Context {
+        options: &options,
+        stats: Stats::default(),
+        tree: None,
+        node: 0,
+        contradiction: None,
+        rng: None,
+        node_limit: Some(node_limit),
+    };
+    let flow = solve(to_solving_board(problem), 0, &mut ctx, &mut |_| {
+        ControlFlow::Continue(())
+    });
+    if flow.is_break() {
+        node_limit
+    } else {
+        ctx.stats.guesses as u64 + 1
+    }
+}
+
+/// Counts solutions of `problem`, stopping the search once `limit` solutions
+/// have been found.
+pub fn count_solutions(problem: &Board<Option<usize>>, limit: usize) -> usize {
+    let mut count = 0;
+    if limit > 0 {
+        let _ = search(problem, &Options::default(), |_| {
+            count += 1;
+            if count < limit {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        });
+    }
+    count
+}
+
+/// Whether `problem` has exactly one solution.
+pub fn is_uniquely_solvable(problem: &Board<Option<usize>>) -> bool {
+    count_solutions(problem, 2) == 1
+}
+
+/// Uniqueness checks of puzzles that share a known solution, as made while
+/// removing clues one by one.
+///
+/// If a puzzle has a unique solution, any other solution of the puzzle with
+/// some clues removed differs from it in a removed cell. When one clue is
+/// removed, the check searches only for a solution with another value in
+/// that cell, which skips the branch of the search that leads to the known
+/// solution, instead of counting solutions from scratch.
+#[derive(Clone, Debug)]
+pub struct UniquenessCheck {
+    solution: Board<usize>,
+    options: Options,
+}
+
+impl UniquenessCheck {
+    /// Creates a check for puzzles whose unique solution is `solution`.
+    pub fn new(solution: Board<usize>) -> UniquenessCheck {
+        UniquenessCheck {
+            solution,
+            options: Options::default(),
+        }
+    }
+
+    /// Whether `problem` is still uniquely solvable after removing the clues
+    /// at `removed`.
+    ///
+    /// `problem` with the values of the solution restored at `removed` must
+    /// be uniquely solvable; otherwise the result is meaningless.
+    pub fn still_unique(&self, problem: &Board<Option<usize>>, removed: &[(usize, usize)]) -> bool {
+        let (i, j) = match *removed {
+            [cell] => cell,
+            // Searching for a solution that differs in each of the cells
+            // repeats much of the work, so counting from scratch is faster.
+            _ => return is_uniquely_solvable(problem),
+        };
+        let mut board = to_solving_board(problem);
+        board.0[i][j].remove(self.solution.0[i][j]);
+        let mut ctx = Context {
+            options: &self.options,
+            stats: Stats::default(),
+            tree: None,
+            node: 0,
+            contradiction: None,
+            rng: None,
+            node_limit: None,
+        };
+        solve(board, 0, &mut ctx, &mut |_| ControlFlow::Break(())).is_continue()
+    }
+}
+
+/// Whether `problem` is uniquely solvable and none of its clues is redundant,
+/// that is, removing any single clue makes the solution non-unique.
+pub fn is_minimal(problem: &Board<Option<usize>>) -> bool {
+    let solution = match solutions_bounded(problem, 2) {
+        (solutions, false) if solutions.len() == 1 => solutions[0],
+        _ => return false,
+    };
+    let check = UniquenessCheck::new(solution);
+
+    let mut board = *problem;
+    for i in 0..N {
+        for j in 0..N {
+            if let Some(n) = board.0[i][j] {
+                board.0[i][j] = None;
+                let unique = check.still_unique(&board, &[(i, j)]);
+                board.0[i][j] = Some(n);
+                if unique {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
+
+/// Returns the clues of `problem` that can each be removed without losing
+/// the uniqueness of the solution, in row-major order.
+///
+/// Redundant clues agree with the solution, so they are not conflicts, yet
+/// they show that the puzzle is not minimal. Each clue is tested alone:
+/// removing all of them at once may leave more than one solution. If
+/// `problem` is not uniquely solvable to begin with, the result is empty.
+pub fn redundant_clues(problem: &Board<Option<usize>>) -> Vec<(usize, usize)> {
+    if !is_uniquely_solvable(problem) {
+        return Vec::new();
+    }
+
+    let mut board = *problem;
+    let mut redundant = Vec::new();
+    for (i, j) in problem.cells_where(Option::is_some) {
+        board.0[i][j] = None;
+        if is_uniquely_solvable(&board) {
+            redundant.push((i, j));
+        }
+        board.0[i][j] = problem.0[i][j];
+    }
+    redundant
+}
+
+/// Returns the pairs of filled cells of `board` that have the same value and
+/// share a row, column, or block.
+///
+/// Each pair is reported once, with the earlier cell in row-major order
+/// first, and the pairs are sorted.
+pub fn conflicts(board: &Board<Option<usize>>) -> Vec<((usize, usize), (usize, usize))> {
+    let mut pairs = Vec::new();
+    for unit in all_units() {
+        let filled: Vec<(usize, usize)> = unit
+            .cells()
+            .filter(|&(i, j)| board.0[i][j].is_some())
+            .collect();
+        for (k, &a) in filled.iter().enumerate() {
+            for &b in &filled[k + 1..] {
+                if board.0[a.0][a.1] == board.0[b.0][b.1] {
+                    pairs.push((a.min(b), a.max(b)));
+                }
+            }
+        }
+    }
+    pairs.sort_unstable();
+    pairs.dedup();
+    pairs
+}
+
+/// First problem that [`verify_solution`] finds in a solution. Cells are
+/// 0-based.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Mismatch {
+    /// The cell has another value than the given of the puzzle, which is
+    /// 0-based.
+    Given { cell: (usize, usize), given: usize },
+    /// The cell has the same value as an earlier cell in the same row,
+    /// column, or block.
+    Repeated {
+        cell: (usize, usize),
+        earlier: (usize, usize),
+    },
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Mismatch::Given { cell, given } => write!(
+                f,
+                "r{}c{} does not have the given {}",
+                cell.0 + 1,
+                cell.1 + 1,
+                given + 1
+            ),
+            Mismatch::Repeated { cell, earlier } => write!(
+                f,
+                "r{}c{} repeats the value of r{}c{}",
+                cell.0 + 1,
+                cell.1 + 1,
+                earlier.0 + 1,
+                earlier.1 + 1
+            ),
+        }
+    }
+}
+
+/// Checks that `solution` is a valid grid that keeps the givens of
+/// `problem`, and returns the problem at the first wrong cell in row-major
+/// order otherwise.
+pub fn verify_solution(
+    problem: &Board<Option<usize>>,
+    solution: &Board<usize>,
+) -> Result<(), Mismatch> {
+    let repeated = conflicts(&solution.map(|&n| Some(n)));
+    for (i, j) in (0..N).flat_map(|i| (0..N).map(move |j| (i, j))) {
+        if let Some(given) = problem.0[i][j].filter(|&given| given != solution.0[i][j]) {
+            return Err(Mismatch::Given {
+                cell: (i, j),
+                given,
+            });
+        }
+        // The earliest pair that ends at this cell.
+        if let Some(&(earlier, cell)) = repeated.iter().find(|&&(_, cell)| cell == (i, j)) {
+            return Err(Mismatch::Repeated { cell, earlier });
+        }
+    }
+    Ok(())
+}
+
+/// Result of [`classify`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Classification {
+    /// Some clue is out of range or repeats another clue in a row, column,
+    /// or block.
+    Invalid,
+    /// The puzzle has exactly one solution.
+    Unique,
+    /// The puzzle has more than one solution.
+    Multiple,
+    /// The clues are consistent with each other, but there is no solution.
+    Unsolvable,
+}
+
+/// Screens `problem`, trying cheap checks before searching.
+///
+/// The clues are checked against each other first, and then propagation
+/// alone decides most puzzles. Only puzzles that propagation cannot finish
+/// are searched, and the search stops at the second solution.
+pub fn classify(problem: &Board<Option<usize>>) -> Classification {
+    let in_range = problem.0.iter().flatten().all(|v| v.is_none_or(|n| n < N));
+    if !in_range || !conflicts(problem).is_empty() {
+        return Classification::Invalid;
+    }
+
+    match solve_logically(problem, &Techniques::default()) {
+        LogicalOutcome::Solved(_) => Classification::Unique,
+        LogicalOutcome::Contradiction(_) => Classification::Unsolvable,
+        LogicalOutcome::Stuck(_) => match count_solutions(problem, 2) {
+            0 => Classification::Unsolvable,
+            1 => Classification::Unique,
+            _ => Classification::Multiple,
+        },
+    }
+}
+
+/// Returns the solutions of `problem` sorted in ascending order without
+/// duplicates.
+///
+/// At most `max` solutions are returned. The second element of the result is
+/// true if `problem` has more solutions than returned.
+pub fn all_solutions_sorted(
+    problem: &Board<Option<usize>>,
+    max: usize,
+) -> (Vec<Board<usize>>, bool) {
+    let (mut solutions, truncated) = solutions_bounded(problem, max);
+    solutions.sort();
+    solutions.dedup();
+    (solutions, truncated)
+}
+
+/// Returns the solutions of `problem` in the order found, stopping after
+/// `cap` solutions.
+///
+/// The second element of the result is true if `problem` has more solutions
+/// than returned. No more than `cap` solutions are kept in memory, so this is
+/// safe for a puzzle with few clues from an untrusted source, which may have
+/// astronomically many solutions.
+pub fn solutions_bounded(problem: &Board<Option<usize>>, cap: usize) -> (Vec<Board<usize>>, bool) {
+    let mut solutions = Vec::new();
+    let mut truncated = false;
+    let _ = search(problem, &Options::default(), |b| {
+        if solutions.len() < cap {
+            solutions.push(b);
+            ControlFlow::Continue(())
+        } else {
+            truncated = true;
+            ControlFlow::Break(())
+        }
+    });
+    (solutions, truncated)
+}
+
+/// Returns the values that every solution of `problem` has, leaving the other
+/// cells empty. If `problem` has no solution, the result is empty.
+///
+/// The solutions are enumerated up to `cap`, or 1 if `cap` is 0, so the cells
+/// that differ are cleared without a search for each cell. If there are more
+/// solutions, each value left is confirmed by searching for a solution
+/// without it, so the result does not depend on `cap` and a hint taken from it
+/// is always right.
+pub fn forced_cells(problem: &Board<Option<usize>>, cap: usize) -> Board<Option<usize>> {
+    let (solutions, truncated) = solutions_bounded(problem, cap.max(1));
+    let mut forced = match solutions.first() {
+        Some(first) => first.map(|&n| Some(n)),
+        None => return Board::empty(),
+    };
+    for solution in &solutions[1..] {
+        for (row, solution_row) in forced.0.iter_mut().zip(&solution.0) {
+            for (cell, &n) in row.iter_mut().zip(solution_row) {
+                if *cell != Some(n) {
+                    *cell = None;
+                }
+            }
+        }
+    }
+
+    if truncated {
+        for i in 0..N {
+            for j in 0..N {
+                if let (Some(n), None) = (forced.0[i][j], problem.0[i][j]) {
+                    let mut board = to_solving_board(problem);
+                    board.0[i][j].remove(n);
+                    let (flow, _) =
+                        search_board(board, &Options::default(), |_| ControlFlow::Break(()));
+                    if flow.is_break() {
+                        forced.0[i][j] = None;
+                    }
+                }
+            }
+        }
+    }
+    forced
+}
+
+// The tests are written for the 9x9 board.
+#[cfg(all(test, not(any(board_4x4, board_6x6, board_16x16, board_25x25))))]
+mod tests {
+
+    use super::*;
+
+    fn parse(s: &str) -> Board<Option<usize>> {
+        let mut board = Board::empty();
+        let mut digits = s.chars().filter_map(|c| c.to_digit(10));
+        for i in 0..N {
+            for j in 0..N {
+                let n = digits.next().unwrap() as usize;
+                board.0[i][j] = if n == 0 { None } else { Some(n - 1) };
+            }
+        }
+        board
+    }
+
+    const CLUES_17: &str =
+        "000000010400000000020000000000050407008000300001090000300400200050100000000806000";
+
+    /// A puzzle that needs many guesses.
+    const HARD: &str =
+        "800000000003600000070090200050007000000045700000100030001000068008500010090000400";
+
+    #[test]
+    fn count_solutions_stops_at_limit() {
+        let empty = Board::empty();
+        assert_eq!(count_solutions(&empty, 0), 0);
+        assert_eq!(count_solutions(&empty, 1), 1);
+        assert_eq!(count_solutions(&empty, 5), 5);
+    }
+
+    /// The solution of `CLUES_17` with the first two rows cleared, which has
+    /// a few solutions.
+    fn ambiguous() -> Board<Option<usize>> {
+        let mut solution = None;
+        for_each_solution(&parse(CLUES_17), |b| solution = Some(b));
+        let mut problem = solution.unwrap().map(|&n| Some(n));
+        problem.0[0] = [None; N];
+        problem.0[1] = [None; N];
+        problem
+    }
+
+    #[test]
+    fn all_solutions_sorted_ambiguous() {
+        let problem = ambiguous();
+        let (solutions, truncated) = all_solutions_sorted(&problem, 1000);
+        assert!(!truncated);
+        assert!(solutions.len() > 1);
+        assert_eq!(solutions.len(), count_solutions(&problem, 1001));
+        assert!(solutions.windows(2).all(|w| w[0] < w[1]));
+
+        let (first, truncated) = all_solutions_sorted(&problem, 1);
+        assert!(truncated);
+        assert_eq!(first.len(), 1);
+    }
+
+    #[test]
+    fn all_solutions_sorted_unique() {
+        let (solutions, truncated) = all_solutions_sorted(&parse(CLUES_17), 1);
+        assert!(!truncated);
+        assert_eq!(solutions.len(), 1);
+        assert_eq!(all_solutions_sorted(&parse(CLUES_17), 0), (vec![], true));
+    }
+
+    #[test]
+    fn naked_singles_without_placing() {
+        let problem = Board::empty().with_clue(0, 0, 1).with_clue(4, 4, 5);
+        let mut board = to_solving_board(&problem);
+        for n in 1..N {
+            board.0[8][8].remove(n);
+        }
+        let before = board;
+        assert_eq!(naked_singles(&board), vec![(0, 0, 0), (4, 4, 4), (8, 8, 0)]);
+        assert_eq!(board, before);
+
+        let board = propagate(&parse(CLUES_17));
+        assert_eq!(naked_singles(&board).len(), N * N);
+    }
+
+    #[test]
+    fn add_clue_matches_propagate() {
+        let mut problem = parse(CLUES_17);
+        problem.0[0][7] = None;
+        let mut board = propagate(&problem);
+        assert!(board.to_solution().is_none());
+        let eliminated = (0..N).find(|&n| !board.0[1][0].can_be(n)).unwrap();
+        let before = board;
+        assert!(!add_clue(&mut board, 1, 0, eliminated));
+        assert_eq!(board, before);
+        assert!(add_clue(&mut board, 0, 7, 0));
+        assert_eq!(board, propagate(&parse(CLUES_17)));
+    }
+
+    #[test]
+    fn consequences_of_placements() {
+        let mut problem = parse(CLUES_17);
+        problem.0[0][7] = None;
+        let board = propagate(&problem);
+        let before = board;
+        let forced = consequences(&board, 0, 7, 0);
+        assert_eq!(board, before);
+        let solution = solve_first(&parse(CLUES_17)).unwrap();
+        let expected: Vec<_> = (0..N * N)
+            .map(|k| (k / N, k % N))
+            .filter(|&(i, j)| (i, j) != (0, 7) && board.0[i][j].get_unique().is_none())
+            .map(|(i, j)| (i, j, solution.0[i][j]))
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(forced, expected);
+
+        // Placing a determined value again forces nothing new.
+        let board = propagate(&parse(CLUES_17));
+        assert_eq!(consequences(&board, 0, 7, 0), []);
+        assert_eq!(consequences(&board, 0, 7, 1), []);
+    }
+
+    #[test]
+    fn sukaku_solutions() {
+        // Candidates of the givens alone are the same as the puzzle.
+        let problem = ambiguous();
+        let mut expected = Vec::new();
+        for_each_solution(&problem, |b| expected.push(b));
+        let mut solutions = Vec::new();
+        for_each_sukaku_solution(&to_solving_board(&problem), |b| solutions.push(b));
+        assert_eq!(solutions, expected);
+
+        // No givens, but every cell has the value of the solution and another.
+        let solution = solve_first(&parse(CLUES_17)).unwrap();
+        let mut rng = Rng::new(5);
+        let mut candidates = solution.map(|&n| SolvingCell::new(Some(n)));
+        for (cell, &n) in candidates
+            .0
+            .iter_mut()
+            .flatten()
+            .zip(solution.0.iter().flatten())
+        {
+            *cell = SolvingCell::from_candidates([n, (n + 1 + rng.below(N - 1)) % N]);
+        }
+        assert!(candidates.0.iter().flatten().all(|c| c.count() == 2));
+        let mut solutions = Vec::new();
+        for_each_sukaku_solution(&candidates, |b| solutions.push(b));
+        assert!(solutions.contains(&solution));
+        for b in &solutions {
+            assert!(b.is_valid_complete());
+            assert!((0..N * N).all(|k| candidates.0[k / N][k % N].can_be(b.0[k / N][k % N])));
+        }
+
+        let mut empty = candidates;
+        empty.0[4][4] = SolvingCell::from_candidates(None);
+        for_each_sukaku_solution(&empty, |b| panic!("{}", b));
+    }
+
+    #[test]
+    fn solving_each_in_order() {
+        let fixtures = std::fs::read_to_string("tests/fixtures/regression.txt").unwrap();
+        let mut problems: Vec<_> = crate::format::parse_sdm(&fixtures)
+            .unwrap()
+            .into_iter()
+            .step_by(2)
+            .collect();
+        let mut unsolvable = problems[0];
+        let (i, j) = unsolvable.cells_where(Option::is_none)[0];
+        unsolvable.0[i][j] = unsolvable.0[i].iter().flatten().next().copied();
+        problems.push(unsolvable);
+        let problems = problems.repeat(5);
+        let expected: Vec<_> = problems.iter().map(solve_first).collect();
+        assert_eq!(expected.last(), Some(&None));
+        for threads in [1, 2, 7] {
+            let mut results = Vec::new();
+            solve_first_each(problems.iter().copied(), threads, |s| results.push(s));
+            assert_eq!(results, expected, "{} threads", threads);
+        }
+    }
+
+    #[test]
+    fn scratch_finds_the_first_solution() {
+        let conflicting = Board::empty().with_clue(0, 0, 1).with_clue(0, 1, 1);
+        let problems = [
+            parse(HARD),
+            Board::empty(),
+            conflicting,
+            ambiguous(),
+            parse(CLUES_17),
+        ];
+        let mut scratch = Scratch::new();
+        for problem in problems.iter().chain(&problems) {
+            assert_eq!(scratch.solve_first(problem), solve_first(problem));
+        }
+        assert!(scratch.branches.is_empty());
+    }
+
+    #[test]
+    fn search_effort_counts_guesses() {
+        let problem = parse(CLUES_17);
+        assert_eq!(search_effort(&problem, 1000), 1);
+
+        let problem = ambiguous();
+        let stats = for_each_solution_with_stats(&problem, &Options::default(), |_| ());
+        let nodes = stats.guesses as u64 + 1;
+        assert!(nodes > 2);
+        assert_eq!(search_effort(&problem, nodes + 1), nodes);
+        assert_eq!(search_effort(&problem, nodes), nodes);
+        assert_eq!(search_effort(&problem, 2), 2);
+        assert_eq!(search_effort(&problem, 0), 0);
+        assert_eq!(search_effort(&Board::empty(), 500), 500);
+    }
+
+    #[test]
+    fn strategies_find_same_solutions() {
+        let problem = ambiguous();
+        let mut solutions = Vec::new();
+        for strategy in [
+            Strategy::FewestCandidates,
+            Strategy::MostConstrainedUnits,
+            Strategy::RowMajor,
+        ] {
+            let options = Options {
+                strategy,
+                ..Options::default()
+            };
+            let mut found = Vec::new();
+            for_each_solution_with(&problem, &options, |b| found.push(b));
+            found.sort();
+            solutions.push(found);
+        }
+        assert!(solutions[0].len() > 1);
+        assert_eq!(solutions[0], solutions[1]);
+        assert_eq!(solutions[0], solutions[2]);
+    }
+
+    #[test]
+    fn row_major_finds_solutions_in_order() {
+        let problem = ambiguous();
+        let options = Options {
+            strategy: Strategy::RowMajor,
+            ..Options::default()
+        };
+        let mut found = Vec::new();
+        for_each_solution_with(&problem, &options, |b| found.push(b));
+        let (sorted, _) = all_solutions_sorted(&problem, 1000);
+        assert_eq!(found, sorted);
+    }
+
+    #[test]
+    fn canonical_solution() {
+        let problem = ambiguous();
+        let (sorted, _) = all_solutions_sorted(&problem, 1000);
+        assert_eq!(solve_canonical(&problem), Some(sorted[0]));
+
+        let problem = parse(CLUES_17);
+        assert_eq!(solve_canonical(&problem), solve_first(&problem));
+
+        let problem = Board::empty().with_clue(0, 0, 1).with_clue(0, 1, 1);
+        assert_eq!(solve_canonical(&problem), None);
+    }
+
+    #[test]
+    fn settled_boards() {
+        let solution = solve_first(&parse(CLUES_17)).unwrap();
+        let board = to_solving_board(&solution.map(|&n| Some(n)));
+        let options = Options::default();
+        assert!(is_settled(&board, &options));
+        let windoku = Options {
+            regions: Region::windoku().to_vec(),
+            ..Options::default()
+        };
+        assert!(!is_settled(&board, &windoku));
+        let anti_knight = Options {
+            anti_knight: true,
+            ..Options::default()
+        };
+        assert!(!is_settled(&board, &anti_knight));
+
+        let mut repeated = board;
+        repeated.0[0][0] = repeated.0[0][1];
+        assert!(!is_settled(&repeated, &options));
+        let mut unsolved = board;
+        unsolved.0[0][0] = SolvingCell::new(None);
+        assert!(!is_settled(&unsolved, &options));
+    }
+
+    #[test]
+    fn solving_skips_the_last_sweep() {
+        let problem = parse(CLUES_17);
+        let stats = for_each_solution_with_stats(&problem, &Options::default(), |_| ());
+        let mut board = to_solving_board(&problem);
+        assert_eq!(stats.sweeps + 1, sweep_all(&mut board, &Options::default()));
+    }
+
+    #[test]
+    fn stats_of_search() {
+        let mut count = 0;
+        let stats = for_each_solution_with_stats(&parse(CLUES_17), &Options::default(), |_| {
+            count += 1;
+        });
+        assert_eq!(count, 1);
+        assert_eq!(stats.guesses, 0);
+        assert_eq!(stats.max_depth, 0);
+        assert!(stats.sweeps > 1);
+
+        let stats = for_each_solution_with_stats(&ambiguous(), &Options::default(), |_| ());
+        assert!(stats.guesses > 1);
+        assert!(stats.max_depth > 0);
+    }
+
+    #[test]
+    fn unique_rectangle_keeps_unique_solution() {
+        let problem = parse(HARD);
+        let options = Options {
+            techniques: Techniques {
+                unique_rectangle: true,
+                ..Techniques::default()
+            },
+            ..Options::default()
+        };
+        let mut solutions = Vec::new();
+        for_each_solution_with(&problem, &options, |b| solutions.push(b));
+        assert_eq!(solutions, vec![solve_first(&problem).unwrap()]);
+    }
+
+    #[test]
+    fn logical_solving() {
+        let problem = parse(CLUES_17);
+        assert_eq!(
+            solve_logically(&problem, &Techniques::default()),
+            LogicalOutcome::Solved(solve_first(&problem).unwrap())
+        );
+        assert_eq!(logical_progress(&problem), 0);
+
+        let problem = parse(HARD);
+        match solve_logically(&problem, &Techniques::default()) {
+            LogicalOutcome::Stuck(board) => assert!(board.to_solution().is_none()),
+            outcome => panic!("{:?}", outcome),
+        }
+        let remaining = logical_progress(&problem);
+        assert!(remaining > 0 && remaining <= N * N - 21);
+
+        let problem = Board::empty().with_clue(0, 0, 1).with_clue(0, 1, 1);
+        assert!(matches!(
+            solve_logically(&problem, &Techniques::default()),
+            LogicalOutcome::Contradiction(_)
+        ));
+    }
+
+    #[test]
+    fn histogram_of_candidates() {
+        let mut expected = [0; N + 1];
+        expected[1] = N * N;
+        assert_eq!(candidate_histogram(&parse(CLUES_17)), expected);
+
+        let histogram = candidate_histogram(&parse(HARD));
+        assert_eq!(histogram.iter().sum::<usize>(), N * N);
+        assert_eq!(histogram[0], 0);
+        assert_eq!(histogram[1], 21);
+
+        let mut expected = [0; N + 1];
+        expected[N] = N * N;
+        assert_eq!(candidate_histogram(&Board::empty()), expected);
+    }
+
+    #[test]
+    fn search_tree_of_guesses() {
+        let tree = search_tree(&parse(CLUES_17), &Options::default());
+        assert_eq!(tree, {
+            let mut tree = SearchTree::new();
+            tree.nodes[0].outcome = Outcome::Solution;
+            tree
+        });
+
+        let problem = parse(HARD);
+        let options = Options::default();
+        let stats = for_each_solution_with_stats(&problem, &options, |_| ());
+        let tree = search_tree(&problem, &options);
+        assert_eq!(tree.nodes.len(), stats.guesses + 1);
+        assert_eq!(tree.nodes[0].outcome, Outcome::Branch);
+        for (k, node) in tree.nodes.iter().enumerate().skip(1) {
+            assert!(node.parent.unwrap() < k);
+            assert!(node.guess.is_some());
+        }
+        let count = |outcome| tree.nodes.iter().filter(|n| n.outcome == outcome).count();
+        assert_eq!(count(Outcome::Solution), 1);
+        assert!(count(Outcome::Contradiction) > 0);
+
+        let options = Options {
+            max_depth: Some(1),
+            ..Options::default()
+        };
+        let tree = search_tree(&problem, &options);
+        let last = tree.nodes.last().unwrap();
+        assert_eq!(last.outcome, Outcome::DepthExceeded);
+    }
+
+    #[test]
+    fn branch_point_of_search() {
+        assert_eq!(branch_point(&parse(CLUES_17)), None);
+        let problem = Board::empty().with_clue(0, 0, 1).with_clue(0, 1, 1);
+        assert_eq!(branch_point(&problem), None);
+
+        let problem = parse(HARD);
+        let (i, j, candidates) = branch_point(&problem).unwrap();
+        assert!(candidates.len() > 1);
+        assert_eq!(problem.0[i][j], None);
+        let tree = search_tree(&problem, &Options::default());
+        let first = tree.nodes[1].guess.unwrap();
+        assert_eq!(first, (i, j, candidates[0]));
+    }
+
+    #[test]
+    fn suggesting_guesses() {
+        let board = match solve_logically(&parse(HARD), &Techniques::default()) {
+            LogicalOutcome::Stuck(board) => board,
+            _ => unreachable!(),
+        };
+        let (i, j, n) = best_guess(&board).unwrap();
+        let (i2, j2, candidates) = branch_point(&parse(HARD)).unwrap();
+        assert_eq!((i, j), (i2, j2));
+        let determined = |m: usize| {
+            let mut after = board;
+            assert!(add_clue(&mut after, i, j, m));
+            let cells = after.0.iter().flatten();
+            (!cells.clone().any(|c| c.count() == 0))
+                .then(|| cells.filter(|c| c.count() == 1).count())
+        };
+        let score = determined(n).unwrap();
+        assert!(candidates
+            .iter()
+            .all(|&m| determined(m).is_none_or(|s| s <= score)));
+
+        assert_eq!(best_guess(&propagate(&parse(CLUES_17))), None);
+        let problem = Board::empty().with_clue(0, 0, 1).with_clue(0, 1, 1);
+        assert_eq!(best_guess(&propagate(&problem)), None);
+    }
+
+    #[test]
+    fn depth_limit() {
+        let problem = parse(HARD);
+        let unlimited = for_each_solution_with_stats(&problem, &Options::default(), |_| ());
+        assert!(!unlimited.depth_exceeded);
+
+        let options = Options {
+            max_depth: Some(unlimited.max_depth),
+            ..Options::default()
+        };
+        let mut count = 0;
+        let stats = for_each_solution_with_stats(&problem, &options, |_| count += 1);
+        assert!(!stats.depth_exceeded);
+        assert_eq!(count, 1);
+
+        let options = Options {
+            max_depth: Some(2),
+            ..Options::default()
+        };
+        let stats = for_each_solution_with_stats(&problem, &options, |_| ());
+        assert!(stats.depth_exceeded);
+        assert!(stats.max_depth <= 2);
+
+        let options = Options {
+            max_depth: Some(0),
+            ..Options::default()
+        };
+        let stats = for_each_solution_with_stats(&parse(CLUES_17), &options, |_| ());
+        assert!(!stats.depth_exceeded);
+    }
+
+    #[test]
+    fn prefilled_naked_singles() {
+        let problem = parse(CLUES_17);
+        let solution = solve_first(&problem).unwrap();
+        let prefilled = prefill_singles(&problem);
+        for (i, j) in (0..N * N).map(|k| (k / N, k % N)) {
+            match (problem.0[i][j], prefilled.0[i][j]) {
+                (Some(n), m) => assert_eq!(m, Some(n)),
+                (None, Some(m)) => assert_eq!(m, solution.0[i][j]),
+                (None, None) => (),
+            }
+        }
+        assert!(!prefilled.cells_where(Option::is_none).is_empty());
+        assert_eq!(prefill_singles(&prefilled), prefilled);
+
+        // The only empty cell of a row is a naked single.
+        let mut row = Board::empty();
+        for j in 1..N {
+            row.0[0][j] = Some(j);
+        }
+        assert_eq!(prefill_singles(&row).0[0][0], Some(0));
+        assert_eq!(prefill_singles(&row).cells_where(Option::is_some).len(), N);
+    }
+
+    #[test]
+    fn bounded_solutions() {
+        let problem = ambiguous();
+        let (all, truncated) = solutions_bounded(&problem, 1000);
+        assert!(!truncated);
+        assert!(all.len() > 2);
+
+        let (some, truncated) = solutions_bounded(&problem, 2);
+        assert!(truncated);
+        assert_eq!(some, all[..2]);
+        assert_eq!(solutions_bounded(&problem, all.len()), (all.clone(), false));
+        assert_eq!(solutions_bounded(&problem, 0), (vec![], true));
+
+        // An empty board has too many solutions to collect.
+        let (some, truncated) = solutions_bounded(&Board::empty(), 100);
+        assert_eq!(some.len(), 100);
+        assert!(truncated);
+    }
+
+    #[test]
+    fn forced_cells_of_ambiguous_puzzle() {
+        let problem = ambiguous();
+        let (all, _) = solutions_bounded(&problem, 1000);
+        let forced = forced_cells(&problem, 1000);
+        for i in 0..N {
+            for j in 0..N {
+                let agreed = all.iter().all(|s| s.0[i][j] == all[0].0[i][j]);
+                assert_eq!(forced.0[i][j], Some(all[0].0[i][j]).filter(|_| agreed));
+            }
+        }
+        assert!(forced.0[0].iter().any(Option::is_none));
+        assert_eq!(forced.0[2..], problem.0[2..]);
+
+        // Fewer solutions than the puzzle has give the same cells.
+        assert_eq!(forced_cells(&problem, 2), forced);
+        assert_eq!(forced_cells(&problem, 0), forced);
+
+        let unique = parse(CLUES_17);
+        assert_eq!(
+            forced_cells(&unique, 2),
+            solve_first(&unique).unwrap().map(|&n| Some(n))
+        );
+        assert_eq!(forced_cells(&Board::empty(), 10), Board::empty());
+        let unsolvable = Board::empty().with_clue(0, 0, 1).with_clue(0, 1, 1);
+        assert_eq!(forced_cells(&unsolvable, 10), Board::empty());
+    }
+
+    #[test]
+    fn solutions_through_channel() {
+        let problem = ambiguous();
+        let mut received: Vec<_> = solutions_channel(&problem).iter().collect();
+        received.sort();
+        assert_eq!(received, all_solutions_sorted(&problem, 1000).0);
+
+        // Dropping the receiver early stops an otherwise endless search.
+        let receiver = solutions_channel(&Board::empty());
+        assert_eq!(receiver.iter().take(3).count(), 3);
+        drop(receiver);
+    }
+
+    #[test]
+    fn seventeen_clues_unique_and_minimal() {
+        let problem = parse(CLUES_17);
+        assert!(is_uniquely_solvable(&problem));
+        assert!(is_minimal(&problem));
+    }
+
+    #[test]
+    fn sixteen_clues_not_unique() {
+        let mut problem = parse(CLUES_17);
+        problem.0[0][7] = None;
+        assert!(!is_uniquely_solvable(&problem));
+        assert!(!is_minimal(&problem));
+    }
+
+    #[test]
+    fn redundant_clue_not_minimal() {
+        let mut problem = parse(CLUES_17);
+        let mut solution = None;
+        for_each_solution(&problem, |b| solution = Some(b));
+        problem.0[0][0] = Some(solution.unwrap().0[0][0]);
+        assert!(is_uniquely_solvable(&problem));
+        assert!(!is_minimal(&problem));
+    }
+
+    #[test]
+    fn over_determined_puzzle() {
+        assert_eq!(redundant_clues(&parse(CLUES_17)), vec![]);
+
+        let mut problem = parse(CLUES_17);
+        let solution = solve_first(&problem).unwrap();
+        let added: Vec<(usize, usize)> = (0..N)
+            .map(|j| (0, j))
+            .filter(|&(i, j)| problem.0[i][j].is_none())
+            .collect();
+        for &(i, j) in &added {
+            problem.0[i][j] = Some(solution.0[i][j]);
+        }
+        assert_eq!(classify(&problem), Classification::Unique);
+        assert_eq!(solve_first(&problem), Some(solution));
+        assert!(!is_minimal(&problem));
+
+        let redundant = redundant_clues(&problem);
+        assert!(
+            added.iter().all(|c| redundant.contains(c)),
+            "{:?}",
+            redundant
+        );
+        for &(i, j) in &redundant {
+            let mut board = problem;
+            board.0[i][j] = None;
+            assert!(is_uniquely_solvable(&board), "({}, {})", i, j);
+        }
+
+        assert_eq!(redundant_clues(&ambiguous()), vec![]);
+    }
+
+    #[test]
+    fn uniqueness_check_agrees_with_counting() {
+        let mut problem = parse(CLUES_17);
+        let solution = solve_first(&problem).unwrap();
+        for j in 0..N {
+            problem.0[0][j] = Some(solution.0[0][j]);
+        }
+        let check = UniquenessCheck::new(solution);
+        let clues = problem.cells_where(|cell| cell.is_some());
+        for &(i, j) in &clues {
+            let mut reduced = problem;
+            reduced.0[i][j] = None;
+            let unique = is_uniquely_solvable(&reduced);
+            assert_eq!(
+                check.still_unique(&reduced, &[(i, j)]),
+                unique,
+                "({}, {})",
+                i,
+                j
+            );
+        }
+
+        let pair = [(0, 0), (0, 1)];
+        let mut reduced = problem;
+        for &(i, j) in &pair {
+            reduced.0[i][j] = None;
+        }
+        assert_eq!(
+            check.still_unique(&reduced, &pair),
+            is_uniquely_solvable(&reduced)
+        );
+    }
+
+    #[test]
+    fn random_first_solutions() {
+        let empty = Board([[None; N]; N]);
+        let grids: Vec<Board<usize>> = (0..8)
+            .map(|seed| solve_first_random(&empty, &mut Rng::new(seed)).unwrap())
+            .collect();
+        assert!(grids.iter().all(Board::is_valid_complete));
+        for (a, grid) in grids.iter().enumerate() {
+            assert!(grids[..a].iter().all(|other| other != grid), "seed {}", a);
+        }
+        assert_eq!(solve_first_random(&empty, &mut Rng::new(3)), Some(grids[3]));
+
+        // A unique solution is found whatever the seed.
+        let problem = parse(CLUES_17);
+        let solution = solve_first(&problem);
+        for seed in 0..4 {
+            assert_eq!(solve_first_random(&problem, &mut Rng::new(seed)), solution);
+        }
+    }
+
+    #[test]
+    fn contradiction_of_unsolvable_puzzle() {
+        let problem = parse(CLUES_17);
+        assert_eq!(solve_or_contradiction(&problem).ok(), solve_first(&problem));
+
+        // A wrong clue that does not repeat any other clue.
+        let solution = solve_first(&problem).unwrap();
+        let (i, j) = (0..N * N)
+            .map(|k| (k / N, k % N))
+            .find(|&(i, j)| problem.0[i][j].is_none())
+            .unwrap();
+        let mut wrong = problem;
+        let n = (0..N)
+            .find(|&n| {
+                n != solution.0[i][j]
+                    && classify(&problem.with_clue(i, j, n + 1)) != Classification::Invalid
+            })
+            .unwrap();
+        wrong.0[i][j] = Some(n);
+        assert_eq!(classify(&wrong), Classification::Unsolvable);
+
+        let board = solve_or_contradiction(&wrong).unwrap_err();
+        assert!(board.0.iter().flatten().any(|c| c.count() == 0));
+        for (i, j) in (0..N * N).map(|k| (k / N, k % N)) {
+            if let Some(n) = wrong.0[i][j] {
+                assert_eq!(board.0[i][j].get_unique(), Some(n));
+            }
+        }
+        let determined =
+            |b: &Board<SolvingCell>| b.0.iter().flatten().filter(|c| c.count() == 1).count();
+        assert!(determined(&board) >= determined(&propagate(&wrong)));
+    }
+
+    #[test]
+    fn comparisons_prune_extremes() {
+        let options = Options {
+            comparisons: vec![Comparison {
+                a: (0, 0),
+                b: (0, 1),
+                a_greater: true,
+            }],
+            ..Options::default()
+        };
+        let mut board = to_solving_board(&Board::empty());
+        sweep_all(&mut board, &options);
+        assert_eq!(
+            board.0[0][0].iter().collect::<Vec<_>>(),
+            (1..N).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            board.0[0][1].iter().collect::<Vec<_>>(),
+            (0..N - 1).collect::<Vec<_>>()
+        );
+        assert_eq!(board.0[0][2].count(), N);
+    }
+
+    #[test]
+    fn comparison_sudoku() {
+        // Signs between the horizontally and vertically adjacent cells in
+        // each block, without any clue.
+        let grid = crate::generator::random_grid(&mut crate::rng::Rng::new(7));
+        let mut comparisons = Vec::new();
+        for (i, j) in (0..N * N).map(|k| (k / N, k % N)) {
+            for (i2, j2) in [(i + 1, j), (i, j + 1)] {
+                if i2 < N && j2 < N && Unit::block_of(i, j) == Unit::block_of(i2, j2) {
+                    comparisons.push(Comparison {
+                        a: (i, j),
+                        b: (i2, j2),
+                        a_greater: grid.0[i][j] > grid.0[i2][j2],
+                    });
+                }
+            }
+        }
+        let options = Options {
+            comparisons,
+            ..Options::default()
+        };
+
+        let mut solutions = Vec::new();
+        let _ = search(&Board::empty(), &options, |b| {
+            solutions.push(b);
+            if solutions.len() < 10 {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        });
+        assert!(solutions.contains(&grid), "{}", solutions.len());
+        for solution in &solutions {
+            assert!(options.comparisons.iter().all(|c| c.holds(solution)));
+        }
+    }
+
+    #[test]
+    fn cages_prune_sums() {
+        let cage = |cells: &[(usize, usize)], sum| Cage {
+            cells: cells.to_vec(),
+            sum,
+        };
+        let options = Options {
+            cages: vec![
+                cage(&[(0, 0), (0, 1)], 3),
+                cage(&[(1, 0), (2, 0)], 17),
+                cage(&[(4, 4), (4, 5), (5, 4)], 8),
+            ],
+            ..Options::default()
+        };
+        let mut board = to_solving_board(&Board::empty());
+        sweep_all(&mut board, &options);
+        let candidates = |i: usize, j: usize| board.0[i][j].iter().collect::<Vec<_>>();
+        assert_eq!(candidates(0, 0), [0, 1]);
+        assert_eq!(candidates(0, 1), [0, 1]);
+        assert_eq!(candidates(1, 0), [7, 8]);
+        // 1 + 2 + 5 and 1 + 3 + 4 are the only ways to make 8.
+        assert_eq!(candidates(4, 4), [0, 1, 2, 3, 4]);
+
+        // A given that leaves no way to make the sum.
+        let problem = Board::empty().with_clue(0, 0, 3);
+        let mut solutions = 0;
+        let _ = search(&problem, &options, |_| {
+            solutions += 1;
+            ControlFlow::Break(())
+        });
+        assert_eq!(solutions, 0);
+    }
+
+    #[test]
+    fn killer_sudoku() {
+        // Each row of each block is a cage, without any clue.
+        let grid = crate::generator::random_grid(&mut crate::rng::Rng::new(7));
+        let cages = (0..N * BLOCK_ROWS)
+            .map(|k| {
+                let (i, j) = (k / BLOCK_ROWS, k % BLOCK_ROWS * BLOCK_COLS);
+                let cells: Vec<_> = (j..j + BLOCK_COLS).map(|j| (i, j)).collect();
+                let sum = cells.iter().map(|&(i, j)| grid.0[i][j] + 1).sum();
+                Cage { cells, sum }
+            })
+            .collect();
+        let options = Options {
+            cages,
+            ..Options::default()
+        };
+        let problem = apply_mask(&grid, &Board(std::array::from_fn(|i| [i % 2 == 0; N])));
+
+        let mut solutions = Vec::new();
+        for_each_solution_with(&problem, &options, |b| solutions.push(b));
+        assert!(solutions.contains(&grid));
+        for solution in &solutions {
+            assert!(options.cages.iter().all(|c| c.holds(solution)));
+        }
+        assert!(solutions.len() < count_solutions(&problem, solutions.len() + 1));
+    }
+
+    #[test]
+    fn template_solution_comes_first() {
+        let problem = ambiguous();
+        let mut solutions = Vec::new();
+        for_each_solution(&problem, |b| solutions.push(b));
+        assert!(solutions.len() > 1);
+
+        for template in &solutions {
+            let mut toward = Vec::new();
+            solve_toward(&problem, template, |b| toward.push(b));
+            assert_eq!(toward[0], *template);
+            assert_eq!(toward.len(), solutions.len());
+            assert!(solutions.iter().all(|b| toward.contains(b)));
+        }
+
+        // A template that is no solution only reorders the guesses.
+        let mut toward = Vec::new();
+        solve_toward(&problem, &Board([[0; N]; N]), |b| toward.push(b));
+        assert_eq!(toward.len(), solutions.len());
+    }
+
+    #[test]
+    fn anti_knight_puzzle() {
+        assert_eq!(knight_cells(0, 0).collect::<Vec<_>>(), [(1, 2), (2, 1)]);
+        assert_eq!(knight_cells(4, 4).count(), 8);
+
+        // Unique under the anti-knight rule with only 12 clues.
+        let problem = parse(
+            "000000000000000000000000000000000000000000007000900003005001008002000904004000360",
+        );
+        let options = Options {
+            anti_knight: true,
+            ..Options::default()
+        };
+        let mut solutions = Vec::new();
+        for_each_solution_with(&problem, &options, |b| solutions.push(b));
+        assert_eq!(solutions.len(), 1);
+        let solution = solutions[0];
+        assert!(solution.is_valid_complete());
+        for (i, j) in (0..N * N).map(|k| (k / N, k % N)) {
+            for (i2, j2) in knight_cells(i, j) {
+                assert_ne!(solution.0[i][j], solution.0[i2][j2]);
+            }
+        }
+        assert!(!is_uniquely_solvable(&problem));
+    }
+
+    #[test]
+    fn windoku_solutions() {
+        let options = Options {
+            regions: Region::windoku().to_vec(),
+            ..Options::default()
+        };
+        let mut first = None;
+        let _ = search(&Board::empty(), &options, |b| {
+            first = Some(b);
+            ControlFlow::Break(())
+        });
+        let first = first.unwrap();
+        assert!(first.is_valid_complete());
+        assert_eq!(options.units().count(), 3 * N + 4);
+        assert!(options.units().skip(3 * N).eq(Region::windoku()));
+        for unit in options.units() {
+            let mut values: Vec<_> = unit.cells().map(|(i, j)| first.0[i][j]).collect();
+            values.sort_unstable();
+            assert_eq!(values, (0..N).collect::<Vec<_>>());
+        }
+
+        // The windows rule out some of the solutions under the standard rules.
+        let mut problem = first.map(|&n| Some(n));
+        for (i, j) in (0..BLOCK_ROWS).flat_map(|i| (0..N).map(move |j| (i, j))) {
+            problem.0[i][j] = None;
+        }
+        let mut standard = Vec::new();
+        for_each_solution(&problem, |b| standard.push(b));
+        let mut windoku = Vec::new();
+        for_each_solution_with(&problem, &options, |b| windoku.push(b));
+        assert!(windoku.contains(&first));
+        assert!(windoku.iter().all(|b| standard.contains(b)));
+        assert!(
+            windoku.len() < standard.len(),
+            "{} {}",
+            windoku.len(),
+            standard.len()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "propagation yielded an invalid solution")]
+    fn invalid_solution_is_caught() {
+        // A board that looks solved, as if a technique had removed too much.
+        let mut board = to_solving_board(&Board([[Some(0); N]; N]));
+        for cell in board.0.iter_mut().flatten() {
+            cell.acknowledge();
+        }
+        let options = Options::default();
+        let mut ctx = Context {
+            options: &options,
+            stats: Stats::default(),
+            tree: None,
+            node: 0,
+            contradiction: None,
+            rng: None,
+            node_limit: None,
+        };
+        let _ = solve(board, 0, &mut ctx, &mut |_| ControlFlow::Continue(()));
+    }
+
+    #[test]
+    fn guessed_cell_is_unsolved() {
+        let mut board = to_solving_board(&Board([[Some(0); N]; N]));
+        let mut pair = SolvingCell::new(None);
+        for n in 2..N {
+            pair.remove(n);
+        }
+        board.0[4][7] = pair;
+        board.0[8][1] = pair;
+        let strategies = [
+            Strategy::FewestCandidates,
+            Strategy::MostConstrainedUnits,
+            Strategy::RowMajor,
+        ];
+        for strategy in strategies {
+            assert_eq!(choose_cell(&board, strategy), 4 * N + 7, "{:?}", strategy);
+        }
+
+        board.0[6][6].remove(0);
+        for strategy in strategies {
+            assert_eq!(choose_cell(&board, strategy), 6 * N + 6, "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "a solved board has no cell to guess")]
+    fn no_cell_to_guess_in_solved_board() {
+        choose_cell(
+            &to_solving_board(&Board([[Some(0); N]; N])),
+            Strategy::default(),
+        );
+    }
+
+    #[test]
+    fn matrix_with_uncertain_cells() {
+        let problem = parse(CLUES_17);
+        let solution = solve_first(&problem).unwrap();
+        let mut matrix = problem.map(|v| v.map_or(0, |n| n as i32 + 1)).0;
+        // An uncertain read and a stray number in empty cells.
+        let empty = problem.cells_where(Option::is_none);
+        matrix[empty[0].0][empty[0].1] = -1;
+        matrix[empty[1].0][empty[1].1] = 42;
+
+        let result = solve_matrix(&matrix).unwrap();
+        assert_eq!(result.solution, solution);
+        assert_eq!(result.inferred, empty);
+
+        matrix[0][0] = 5;
+        matrix[0][1] = 5;
+        assert_eq!(solve_matrix(&matrix), None);
+    }
+
+    #[test]
+    fn conflicting_pairs() {
+        assert_eq!(conflicts(&parse(CLUES_17)), vec![]);
+
+        // (0, 0) and (1, 1) share only a block, (0, 0) and (0, 2) share a row
+        // and a block, and (0, 2) and (8, 2) share a column.
+        let board = Board::empty()
+            .with_clue(0, 0, 4)
+            .with_clue(1, 1, 4)
+            .with_clue(0, 2, 4)
+            .with_clue(8, 2, 4)
+            .with_clue(8, 8, 5);
+        assert_eq!(
+            conflicts(&board),
+            vec![
+                ((0, 0), (0, 2)),
+                ((0, 0), (1, 1)),
+                ((0, 2), (1, 1)),
+                ((0, 2), (8, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn verifying_solutions() {
+        let problem = parse(CLUES_17);
+        let solution = solve_first(&problem).unwrap();
+        assert_eq!(verify_solution(&problem, &solution), Ok(()));
+
+        // Swapping two cells keeps the rows valid, but not the columns.
+        let mut swapped = solution;
+        swapped.0[4].swap(3, 5);
+        assert_eq!(
+            verify_solution(&Board::empty(), &swapped),
+            Err(Mismatch::Repeated {
+                cell: (4, 3),
+                earlier: (
+                    swapped
+                        .0
+                        .iter()
+                        .position(|row| row[3] == swapped.0[4][3])
+                        .unwrap(),
+                    3
+                ),
+            })
+        );
+
+        let (i, j) = problem.cells_where(|cell| cell.is_some())[3];
+        let mut wrong = solution;
+        wrong.0[i][j] = (wrong.0[i][j] + 1) % N;
+        let mismatch = verify_solution(&problem, &wrong).unwrap_err();
+        assert_eq!(
+            mismatch,
+            Mismatch::Given {
+                cell: (i, j),
+                given: solution.0[i][j],
+            }
+        );
+        assert_eq!(
+            mismatch.to_string(),
+            format!(
+                "r{}c{} does not have the given {}",
+                i + 1,
+                j + 1,
+                solution.0[i][j] + 1
+            )
+        );
+    }
+
+    #[test]
+    fn classifications() {
+        assert_eq!(classify(&parse(CLUES_17)), Classification::Unique);
+        assert_eq!(classify(&Board::empty()), Classification::Multiple);
+
+        let conflicting = Board::empty().with_clue(0, 0, 3).with_clue(2, 2, 3);
+        assert_eq!(classify(&conflicting), Classification::Invalid);
+
+        // The clues do not repeat, but no value fits in the top-left cell.
+        let mut unsolvable = Board::empty();
+        for j in 1..N {
+            unsolvable.0[0][j] = Some(j - 1);
+        }
+        unsolvable.0[1][0] = Some(N - 1);
+        assert_eq!(classify(&unsolvable), Classification::Unsolvable);
+    }
+
+    #[test]
+    fn try_for_each_solution_runs_to_the_end() {
+        let problem = ambiguous();
+        let mut count = 0;
+        let flow = try_for_each_solution(&problem, &Options::default(), |_| {
+            count += 1;
+            ControlFlow::Continue(())
+        });
+        assert_eq!(flow, ControlFlow::Continue(()));
+        assert_eq!(count, count_solutions(&problem, usize::MAX));
+    }
+
+    /// `grid` with the first band moved to the bottom and the first stack
+    /// moved to the right, so that its top-left block becomes the
+    /// bottom-right one.
+    fn rotate_bands(grid: &Board<usize>) -> Board<usize> {
+        Board(std::array::from_fn(|i| {
+            std::array::from_fn(|j| grid.0[(i + 3) % N][(j + 3) % N])
+        }))
+    }
+
+    #[test]
+    fn solving_overlapping_boards() {
+        // The first board has a few solutions, which the second decides.
+        let first = ambiguous();
+        let grid = solve_first(&parse(CLUES_17)).unwrap();
+        let mut second = rotate_bands(&grid).map(|&n| Some(n));
+        for (i, j) in block_iter(6, 6) {
+            second.0[i][j] = None;
+        }
+        let overlap = Overlap {
+            first: (0, 0),
+            second: (6, 6),
+        };
+        assert!(count_solutions(&first, 2) > 1);
+        assert_eq!(
+            solve_overlapping(&first, &second, overlap),
+            Some((grid, rotate_bands(&grid)))
+        );
+
+        // Any solution of an empty second board must agree with the first.
+        let (solution1, solution2) = solve_overlapping(&first, &Board::empty(), overlap).unwrap();
+        assert!(solution1.is_valid_complete());
+        assert!(solution2.is_valid_complete());
+        assert!(overlap
+            .cells()
+            .all(|((i1, j1), (i2, j2))| solution1.0[i1][j1] == solution2.0[i2][j2]));
+        assert!((0..N * N)
+            .all(|k| first.0[k / N][k % N].is_none_or(|n| n == solution1.0[k / N][k % N])));
+
+        // Each board is solvable alone, but not with the other.
+        let full = grid.map(|&n| Some(n));
+        let mut conflicting = Board::empty();
+        conflicting.0[6][6] = Some((grid.0[0][0] + 1) % N);
+        assert_eq!(solve_overlapping(&full, &conflicting, overlap), None);
+    }
+
+    #[test]
+    fn sweep_orders_find_same_solutions() {
+        for puzzle in [parse(HARD), ambiguous()] {
+            let mut expected = Vec::new();
+            for_each_solution(&puzzle, |b| expected.push(b));
+            expected.sort();
+
+            let options = Options {
+                sweep_order: SweepOrder::CellsFirst,
+                ..Options::default()
+            };
+            let mut solutions = Vec::new();
+            let stats = for_each_solution_with_stats(&puzzle, &options, |b| solutions.push(b));
+            solutions.sort();
+            assert_eq!(solutions, expected);
+            assert!(stats.sweeps > 0);
+        }
+    }
+
+    #[test]
+    fn solutions_where_predicate_holds() {
+        let problem = ambiguous();
+        let mut all = Vec::new();
+        for_each_solution(&problem, |b| all.push(b));
+        let corner = all[0].0[0][0];
+
+        let mut filtered = Vec::new();
+        for_each_solution_where(&problem, |b| b.0[0][0] == corner, |b| filtered.push(b));
+        all.retain(|b| b.0[0][0] == corner);
+        assert_eq!(filtered, all);
+    }
 }
@@ -0,0 +1,737 @@
+//! Solving step by step, recording each deduction.
+//!
+//! Unlike the solver, which applies every deduction it can find in a sweep,
+//! [`solve_steps`] makes one deduction at a time, always the easiest one
+//! available, so the steps are in the order a person would find them.
+
+use crate::board::*;
+use crate::solver::choose_cell;
+use crate::solver::examine_cell;
+use crate::solver::to_solving_board;
+use crate::solver::Strategy;
+use crate::techniques::eliminate;
+use crate::techniques::Technique;
+use crate::techniques::Techniques;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Deduction or guess made by [`solve_steps`].
+///
+/// Rows, columns, and values are 0-based.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Step {
+    /// The cell has no candidate other than `value` left.
+    NakedSingle {
+        row: usize,
+        col: usize,
+        value: usize,
+    },
+    /// The cell is the only one in `unit` that can take `value`.
+    HiddenSingle {
+        row: usize,
+        col: usize,
+        value: usize,
+        unit: Unit,
+    },
+    /// `technique` removed candidates, listed as `(row, col, value)`.
+    Elimination {
+        technique: Technique,
+        removed: Vec<(usize, usize, usize)>,
+    },
+    /// `value` is assumed at the cell since no deduction could be made. The
+    /// assumption turns out right, since only the steps leading to the
+    /// solution are recorded.
+    Guess {
+        row: usize,
+        col: usize,
+        value: usize,
+    },
+}
+
+/// Label of a cell in the RxCy notation, with 1-based row and column.
+fn cell_label(i: usize, j: usize) -> String {
+    format!("R{}C{}", i + 1, j + 1)
+}
+
+/// Joins `items` as in "a, b and c".
+fn join_and(items: &[String]) -> String {
+    match items.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+/// Explains the step in a sentence, with cells in the RxCy notation and
+/// 1-based values.
+///
+/// ```
+/// # use sudoku_solver_in_rust::board::Unit;
+/// # use sudoku_solver_in_rust::steps::Step;
+/// let step = Step::HiddenSingle {
+///     row: 2,
+///     col: 4,
+///     value: 6,
+///     unit: Unit::Block(0, 1),
+/// };
+/// assert_eq!(
+///     step.to_string(),
+///     "R3C5 is the only cell in block 2 that can be 7, so place 7."
+/// );
+/// ```
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Step::NakedSingle { row, col, value } => write!(
+                f,
+                "{} cannot be anything but {}, so place {}.",
+                cell_label(*row, *col),
+                value + 1,
+                value + 1
+            ),
+            Step::HiddenSingle {
+                row,
+                col,
+                value,
+                unit,
+            } => write!(
+                f,
+                "{} is the only cell in {} that can be {}, so place {}.",
+                cell_label(*row, *col),
+                unit,
+                value + 1,
+                value + 1
+            ),
+            Step::Elimination { technique, removed } => {
+                // Group the cells by value, in the order the values appear.
+                let mut values: Vec<usize> = Vec::new();
+                for &(_, _, n) in removed {
+                    if !values.contains(&n) {
+                        values.push(n);
+                    }
+                }
+                let groups: Vec<String> = values
+                    .iter()
+                    .map(|&n| {
+                        let cells: Vec<String> = removed
+                            .iter()
+                            .filter(|&&(_, _, m)| m == n)
+                            .map(|&(i, j, _)| cell_label(i, j))
+                            .collect();
+                        format!("{} from {}", n + 1, join_and(&cells))
+                    })
+                    .collect();
+                let technique = technique.to_string();
+                let mut name = technique.chars();
+                let capitalized: String = name
+                    .next()
+                    .map(|c| c.to_uppercase().chain(name).collect())
+                    .unwrap_or_default();
+                write!(f, "{} removes {}.", capitalized, groups.join("; "))
+            }
+            Step::Guess { row, col, value } => write!(
+                f,
+                "No deduction is left, so assume {} is {}.",
+                cell_label(*row, *col),
+                value + 1
+            ),
+        }
+    }
+}
+
+/// Returns the steps that solve `problem`, as [`solve_steps`] makes them,
+/// explained in sentences.
+pub fn explain(problem: &Board<Option<usize>>, techniques: &Techniques) -> Option<Vec<String>> {
+    let (_, steps) = solve_steps(problem, techniques)?;
+    Some(steps.iter().map(Step::to_string).collect())
+}
+
+/// Places `n` at `(i, j)` and removes it from the peers.
+fn place(board: &mut Board<SolvingCell>, i: usize, j: usize, n: usize) {
+    board.0[i][j] = SolvingCell::new(Some(n));
+    examine_cell(board, i, j, &[]);
+}
+
+/// Makes the easiest deduction available in `board`, if any.
+fn next_step(board: &mut Board<SolvingCell>, techniques: &Techniques) -> Option<Step> {
+    for i in 0..N {
+        for j in 0..N {
+            if board.0[i][j].has_update() {
+                if let Some(value) = board.0[i][j].get_unique() {
+                    examine_cell(board, i, j, &[]);
+                    return Some(Step::NakedSingle {
+                        row: i,
+                        col: j,
+                        value,
+                    });
+                }
+            }
+        }
+    }
+
+    for unit in all_units() {
+        for n in 0..N {
+            let mut cells = unit.cells().filter(|&(i, j)| board.0[i][j].can_be(n));
+            if let (Some((i, j)), None) = (cells.next(), cells.next()) {
+                if board.0[i][j].count() > 1 {
+                    place(board, i, j, n);
+                    return Some(Step::HiddenSingle {
+                        row: i,
+                        col: j,
+                        value: n,
+                        unit,
+                    });
+                }
+            }
+        }
+    }
+
+    for technique in techniques.iter() {
+        let removed = technique.find(board);
+        if eliminate(board, &removed) {
+            return Some(Step::Elimination { technique, removed });
+        }
+    }
+
+    None
+}
+
+/// Whether some cell has no candidate or some unit has no place for some
+/// value.
+fn is_contradiction(board: &Board<SolvingCell>) -> bool {
+    board.0.iter().flatten().any(|cell| cell.count() == 0)
+        || all_units()
+            .any(|unit| (0..N).any(|n| !unit.cells().any(|(i, j)| board.0[i][j].can_be(n))))
+}
+
+/// Work done by [`solve_from`], including in the branches that fail.
+#[derive(Clone, Copy, Debug, Default)]
+struct Effort {
+    guesses: usize,
+    max_depth: usize,
+    deductions: usize,
+}
+
+fn solve_from(
+    mut board: Board<SolvingCell>,
+    techniques: &Techniques,
+    steps: &mut Vec<Step>,
+    depth: usize,
+    effort: &mut Effort,
+) -> Option<Board<usize>> {
+    effort.max_depth = effort.max_depth.max(depth);
+    while !is_contradiction(&board) {
+        match next_step(&mut board, techniques) {
+            Some(step) => {
+                effort.deductions += 1;
+                steps.push(step)
+            }
+            None => break,
+        }
+    }
+    if is_contradiction(&board) {
+        return None;
+    }
+    if let Some(solution) = board.to_solution() {
+        return Some(solution);
+    }
+
+    let k = choose_cell(&board, Strategy::default());
+    let (i, j) = (k / N, k % N);
+    for value in board.0[i][j].iter() {
+        let len = steps.len();
+        steps.push(Step::Guess {
+            row: i,
+            col: j,
+            value,
+        });
+        effort.guesses += 1;
+        let mut board2 = board;
+        place(&mut board2, i, j, value);
+        if let Some(solution) = solve_from(board2, techniques, steps, depth + 1, effort) {
+            return Some(solution);
+        }
+        steps.truncate(len);
+    }
+    None
+}
+
+/// Solves `problem` one step at a time, using singles and the enabled
+/// `techniques`, and guessing when they do not make progress.
+///
+/// Returns the first solution found with the steps leading to it, or `None`
+/// if there is no solution. This is much slower than the solver, so use it
+/// only when the steps are of interest.
+pub fn solve_steps(
+    problem: &Board<Option<usize>>,
+    techniques: &Techniques,
+) -> Option<(Board<usize>, Vec<Step>)> {
+    let mut steps = Vec::new();
+    let mut effort = Effort::default();
+    let solution = solve_from(
+        initial_board(problem),
+        techniques,
+        &mut steps,
+        0,
+        &mut effort,
+    )?;
+    Some((solution, steps))
+}
+
+/// Solves `problem` like [`solve_steps`] and returns the board after each
+/// placement, for rendering the solving as an animation.
+///
+/// The first frame is `problem` and each following one has one more cell
+/// filled, by a single or a guess, until the last is the solution. The
+/// eliminations of the techniques place no value, so they add no frames.
+/// Every frame is a partial board of the solution.
+pub fn solving_frames(
+    problem: &Board<Option<usize>>,
+    techniques: &Techniques,
+) -> Option<Vec<Board<Option<usize>>>> {
+    let (_, steps) = solve_steps(problem, techniques)?;
+    let mut frame = *problem;
+    let mut frames = vec![frame];
+    for step in &steps {
+        match *step {
+            Step::NakedSingle { row, col, value }
+            | Step::HiddenSingle {
+                row, col, value, ..
+            }
+            | Step::Guess { row, col, value } => {
+                frame.0[row][col] = Some(value);
+                frames.push(frame);
+            }
+            Step::Elimination { .. } => (),
+        }
+    }
+    Some(frames)
+}
+
+/// Returns the board of `problem` with the values of the clues removed from
+/// their peers, which is where the steps start.
+fn initial_board(problem: &Board<Option<usize>>) -> Board<SolvingCell> {
+    let mut board = to_solving_board(problem);
+    for (i, j) in problem.cells_where(Option::is_some) {
+        examine_cell(&mut board, i, j, &[]);
+    }
+    board
+}
+
+/// Hint returned by [`hints_in_order`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Hint {
+    /// The next deduction, which is never a [`Step::Guess`].
+    Step(Step),
+    /// No deduction can be made from here, so logic alone cannot solve the
+    /// puzzle.
+    Stuck,
+}
+
+/// Returns the deductions that solve `problem` with singles and the enabled
+/// `techniques`, in the order [`solve_steps`] makes them, so that the hints
+/// can be revealed one at a time.
+///
+/// Each step is the easiest available at that point. If the deductions stall
+/// before the puzzle is solved, the last hint is [`Hint::Stuck`]; this is
+/// also the case if the puzzle turns out to have no solution.
+pub fn hints_in_order(problem: &Board<Option<usize>>, techniques: &Techniques) -> Vec<Hint> {
+    let mut board = initial_board(problem);
+    let mut hints = Vec::new();
+    while !is_contradiction(&board) {
+        match next_step(&mut board, techniques) {
+            Some(step) => hints.push(Hint::Step(step)),
+            None => break,
+        }
+    }
+    if board.to_solution().is_none() {
+        hints.push(Hint::Stuck);
+    }
+    hints
+}
+
+/// How many times each kind of step was made in solving a puzzle.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TechniqueUsage {
+    /// Number of cells placed as naked singles.
+    pub naked_singles: usize,
+    /// Number of cells placed as hidden singles.
+    pub hidden_singles: usize,
+    /// Number of times each technique removed candidates.
+    pub eliminations: BTreeMap<Technique, usize>,
+    /// Number of cells placed by guessing.
+    pub guesses: usize,
+}
+
+impl TechniqueUsage {
+    /// Counts the steps.
+    pub fn from_steps(steps: &[Step]) -> Self {
+        let mut usage = TechniqueUsage::default();
+        for step in steps {
+            match step {
+                Step::NakedSingle { .. } => usage.naked_singles += 1,
+                Step::HiddenSingle { .. } => usage.hidden_singles += 1,
+                Step::Elimination { technique, .. } => {
+                    *usage.eliminations.entry(*technique).or_insert(0) += 1
+                }
+                Step::Guess { .. } => usage.guesses += 1,
+            }
+        }
+        usage
+    }
+}
+
+/// Like [`solve_steps`], but returns how many times each kind of step was
+/// made instead of the steps.
+pub fn solve_with_usage(
+    problem: &Board<Option<usize>>,
+    techniques: &Techniques,
+) -> Option<(Board<usize>, TechniqueUsage)> {
+    let (solution, steps) = solve_steps(problem, techniques)?;
+    Some((solution, TechniqueUsage::from_steps(&steps)))
+}
+
+/// Metrics of solving a puzzle, returned by [`solve_and_collect_stats`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SolveStats {
+    /// Number of values assumed, including the wrong ones.
+    pub guesses: usize,
+    /// Deepest nesting of guesses, which is 0 if no guess was needed.
+    pub max_depth: usize,
+    /// Number of deductions made, including those in branches that failed.
+    /// Each deduction is an iteration of the step solver, which takes the
+    /// place of a sweep of the solver.
+    pub deductions: usize,
+    /// Time the solving took.
+    pub elapsed: Duration,
+    /// Steps leading to the solution, counted by kind. This is empty if there
+    /// is no solution.
+    pub usage: TechniqueUsage,
+    /// The last of [`Technique::ALL`] in `usage`, if any technique was used.
+    pub hardest: Option<Technique>,
+}
+
+/// Solves `problem` with singles, all the techniques, and guessing, and
+/// returns the first solution found, if any, with everything measured along
+/// the way.
+///
+/// This is a single pass of [`solve_steps`], so it is cheaper than calling
+/// the separate functions for each metric, but much slower than
+/// [`solve_first`](crate::solver::solve_first).
+pub fn solve_and_collect_stats(
+    problem: &Board<Option<usize>>,
+) -> (Option<Board<usize>>, SolveStats) {
+    let techniques = Technique::ALL.iter().copied().collect();
+    let start = Instant::now();
+    let mut steps = Vec::new();
+    let mut effort = Effort::default();
+    let solution = solve_from(
+        initial_board(problem),
+        &techniques,
+        &mut steps,
+        0,
+        &mut effort,
+    );
+    let elapsed = start.elapsed();
+
+    // The steps of failed branches are removed on backtracking, so what is
+    // left leads to the solution.
+    let usage = if solution.is_some() {
+        TechniqueUsage::from_steps(&steps)
+    } else {
+        TechniqueUsage::default()
+    };
+    let hardest = Technique::ALL
+        .iter()
+        .rev()
+        .copied()
+        .find(|technique| usage.eliminations.contains_key(technique));
+    let stats = SolveStats {
+        guesses: effort.guesses,
+        max_depth: effort.max_depth,
+        deductions: effort.deductions,
+        elapsed,
+        usage,
+        hardest,
+    };
+    (solution, stats)
+}
+
+/// Number of steps that singles and the enabled `techniques` take to solve
+/// `problem`, where each placement and each use of a technique is a step.
+///
+/// This is a finer measure of difficulty than the techniques needed. If the
+/// puzzle cannot be solved without guessing, or has no solution at all, the
+/// result is `usize::MAX`, which ranks it harder than any other.
+pub fn solving_path_length(problem: &Board<Option<usize>>, techniques: &Techniques) -> usize {
+    match solve_steps(problem, techniques) {
+        Some((_, steps)) if !steps.iter().any(|s| matches!(s, Step::Guess { .. })) => steps.len(),
+        _ => usize::MAX,
+    }
+}
+
+// The tests are written for the 9x9 board.
+#[cfg(all(test, not(any(board_4x4, board_6x6, board_16x16, board_25x25))))]
+mod tests {
+
+    use super::*;
+    use crate::format::parse_sdm;
+    use crate::generator::clue_count;
+    use crate::solver::solve_first;
+
+    fn parse(line: &str) -> Board<Option<usize>> {
+        parse_sdm(line).unwrap()[0]
+    }
+
+    /// Checks that `steps` solve `problem` into `solution`.
+    fn check_steps(problem: &Board<Option<usize>>, solution: &Board<usize>, steps: &[Step]) {
+        let mut board = *problem;
+        for step in steps {
+            match *step {
+                Step::NakedSingle { row, col, value }
+                | Step::HiddenSingle {
+                    row, col, value, ..
+                }
+                | Step::Guess { row, col, value } => {
+                    assert_eq!(board.0[row][col], None, "{:?}", step);
+                    assert_eq!(solution.0[row][col], value, "{:?}", step);
+                    board.0[row][col] = Some(value);
+                }
+                Step::Elimination { ref removed, .. } => {
+                    assert!(!removed.is_empty());
+                    for &(i, j, n) in removed {
+                        assert_ne!(solution.0[i][j], n, "{:?}", step);
+                    }
+                }
+            }
+        }
+        assert_eq!(board, solution.map(|&n| Some(n)));
+    }
+
+    #[test]
+    fn steps_of_easy_puzzle() {
+        let problem = parse(
+            "100400709050780020709023006300600000640070012908002045230504800060090030807001064",
+        );
+        let (solution, steps) = solve_steps(&problem, &Techniques::default()).unwrap();
+        assert_eq!(Some(solution), solve_first(&problem));
+        check_steps(&problem, &solution, &steps);
+
+        let usage = TechniqueUsage::from_steps(&steps);
+        assert_eq!(usage.guesses, 0);
+        assert_eq!(
+            usage.naked_singles + usage.hidden_singles,
+            N * N - clue_count(&problem)
+        );
+    }
+
+    #[test]
+    fn steps_with_guesses() {
+        let problem = parse(
+            "800000000003600000070090200050007000000045700000100030001000068008500010090000400",
+        );
+        let (solution, usage) = solve_with_usage(&problem, &Techniques::default()).unwrap();
+        assert_eq!(Some(solution), solve_first(&problem));
+        assert!(usage.guesses > 0);
+        assert_eq!(
+            usage.naked_singles + usage.hidden_singles + usage.guesses,
+            N * N - clue_count(&problem)
+        );
+        let (_, steps) = solve_steps(&problem, &Techniques::default()).unwrap();
+        check_steps(&problem, &solution, &steps);
+    }
+
+    #[test]
+    fn steps_with_technique() {
+        let problem = parse(
+            "000109000000030002080400907069070510000000000405000700000000090007504600896001000",
+        );
+        let techniques = Techniques {
+            finned_x_wing: true,
+            ..Techniques::default()
+        };
+        let (solution, steps) = solve_steps(&problem, &techniques).unwrap();
+        check_steps(&problem, &solution, &steps);
+        let usage = TechniqueUsage::from_steps(&steps);
+        assert_eq!(usage.guesses, 0);
+        assert!(usage.eliminations[&Technique::FinnedXWing] > 0);
+    }
+
+    #[test]
+    fn no_steps_without_solution() {
+        let problem = Board::empty().with_clue(0, 0, 1).with_clue(0, 1, 1);
+        assert_eq!(solve_steps(&problem, &Techniques::default()), None);
+        assert_eq!(
+            solving_path_length(&problem, &Techniques::default()),
+            usize::MAX
+        );
+    }
+
+    #[test]
+    fn hints_of_easy_and_hard_puzzles() {
+        let easy = parse(
+            "100400709050780020709023006300600000640070012908002045230504800060090030807001064",
+        );
+        let (_, steps) = solve_steps(&easy, &Techniques::default()).unwrap();
+        assert_eq!(
+            hints_in_order(&easy, &Techniques::default()),
+            steps.into_iter().map(Hint::Step).collect::<Vec<_>>()
+        );
+
+        let hard = parse(
+            "800000000003600000070090200050007000000045700000100030001000068008500010090000400",
+        );
+        let solution = solve_first(&hard).unwrap();
+        let mut hints = hints_in_order(&hard, &Techniques::default());
+        assert_eq!(hints.pop(), Some(Hint::Stuck));
+        let mut partial = hard;
+        for hint in hints {
+            match hint {
+                Hint::Step(Step::NakedSingle { row, col, value })
+                | Hint::Step(Step::HiddenSingle {
+                    row, col, value, ..
+                }) => {
+                    assert_eq!(solution.0[row][col], value);
+                    partial.0[row][col] = Some(value);
+                }
+                hint => panic!("unexpected hint {:?}", hint),
+            }
+        }
+        assert!(partial.cells_where(Option::is_none).len() > 1);
+    }
+
+    #[test]
+    fn explanations() {
+        let naked = Step::NakedSingle {
+            row: 0,
+            col: 8,
+            value: 0,
+        };
+        assert_eq!(
+            naked.to_string(),
+            "R1C9 cannot be anything but 1, so place 1."
+        );
+        let elimination = Step::Elimination {
+            technique: Technique::Claiming,
+            removed: vec![(0, 1, 3), (3, 1, 4), (0, 2, 3), (1, 1, 3)],
+        };
+        assert_eq!(
+            elimination.to_string(),
+            "Claiming removes 4 from R1C2, R1C3 and R2C2; 5 from R4C2."
+        );
+        let guess = Step::Guess {
+            row: 4,
+            col: 4,
+            value: 8,
+        };
+        assert_eq!(
+            guess.to_string(),
+            "No deduction is left, so assume R5C5 is 9."
+        );
+
+        let problem = parse(
+            "100400709050780020709023006300600000640070012908002045230504800060090030807001064",
+        );
+        let sentences = explain(&problem, &Techniques::default()).unwrap();
+        assert_eq!(sentences.len(), N * N - clue_count(&problem));
+        assert!(sentences
+            .iter()
+            .all(|s| s.starts_with('R') && s.ends_with('.')));
+        assert_eq!(
+            explain(
+                &Board::empty().with_clue(1, 1, 1).with_clue(1, 2, 1),
+                &Techniques::default()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn frames_of_solving() {
+        let hard = parse(
+            "800000000003600000070090200050007000000045700000100030001000068008500010090000400",
+        );
+        let frames = solving_frames(&hard, &Techniques::default()).unwrap();
+        let solution = solve_first(&hard).unwrap();
+        assert_eq!(frames[0], hard);
+        assert_eq!(frames.last(), Some(&solution.map(|&n| Some(n))));
+        assert_eq!(frames.len(), N * N - clue_count(&hard) + 1);
+        for pair in frames.windows(2) {
+            let filled = |b: &Board<Option<usize>>| b.cells_where(Option::is_some);
+            assert_eq!(filled(&pair[1]).len(), filled(&pair[0]).len() + 1);
+            assert!(filled(&pair[0])
+                .into_iter()
+                .all(|(i, j)| pair[1].0[i][j] == pair[0].0[i][j]));
+        }
+        assert!(frames.iter().all(|frame| (0..N * N)
+            .all(|k| frame.0[k / N][k % N].is_none_or(|n| n == solution.0[k / N][k % N]))));
+
+        let unsolvable = Board::empty().with_clue(0, 0, 1).with_clue(0, 1, 1);
+        assert_eq!(solving_frames(&unsolvable, &Techniques::default()), None);
+    }
+
+    #[test]
+    fn collected_stats() {
+        let problem = parse(
+            "000109000000030002080400907069070510000000000405000700000000090007504600896001000",
+        );
+        let (solution, stats) = solve_and_collect_stats(&problem);
+        assert_eq!(solution, solve_first(&problem));
+        let all = Technique::ALL.iter().copied().collect();
+        let (_, usage) = solve_with_usage(&problem, &all).unwrap();
+        assert_eq!(stats.usage, usage);
+        assert_eq!((stats.guesses, stats.max_depth), (0, 0));
+        assert_eq!(stats.deductions, solving_path_length(&problem, &all));
+        assert!(stats.hardest.is_some());
+        assert_eq!(
+            stats
+                .hardest
+                .map(|t| usage.eliminations.keys().all(|&u| u <= t)),
+            Some(true)
+        );
+
+        let hard = parse(
+            "800000000003600000070090200050007000000045700000100030001000068008500010090000400",
+        );
+        let (solution, stats) = solve_and_collect_stats(&hard);
+        assert_eq!(solution, solve_first(&hard));
+        assert!(stats.guesses >= stats.usage.guesses);
+        assert!(stats.max_depth >= 1);
+        assert!(stats.deductions >= stats.usage.naked_singles + stats.usage.hidden_singles);
+
+        let (solution, stats) =
+            solve_and_collect_stats(&Board::empty().with_clue(0, 0, 1).with_clue(0, 1, 1));
+        assert_eq!(solution, None);
+        assert_eq!(stats.usage, TechniqueUsage::default());
+        assert_eq!(stats.hardest, None);
+    }
+
+    #[test]
+    fn path_lengths() {
+        let easy = parse(
+            "100400709050780020709023006300600000640070012908002045230504800060090030807001064",
+        );
+        assert_eq!(
+            solving_path_length(&easy, &Techniques::default()),
+            N * N - clue_count(&easy)
+        );
+
+        let problem = parse(
+            "000109000000030002080400907069070510000000000405000700000000090007504600896001000",
+        );
+        assert_eq!(
+            solving_path_length(&problem, &Techniques::default()),
+            usize::MAX
+        );
+        let techniques = Techniques {
+            finned_x_wing: true,
+            ..Techniques::default()
+        };
+        let length = solving_path_length(&problem, &techniques);
+        assert!(length > N * N - clue_count(&problem), "{}", length);
+        assert_ne!(length, usize::MAX);
+    }
+}
@@ -0,0 +1,1069 @@
+//! Deduction techniques beyond singles.
+//!
+//! The solver always finds naked and hidden singles. The techniques here
+//! are optional and enabled through [`Techniques`].
+
+use crate::board::*;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::iter::FromIterator;
+
+/// Set of optional techniques that the solver uses when singles do not make
+/// progress.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Techniques {
+    /// Locked candidates type 2, also known as claiming.
+    pub claiming: bool,
+    /// Unique rectangle type 1.
+    ///
+    /// This assumes the puzzle has a unique solution; enabling it for a
+    /// puzzle with more solutions may lose some of them.
+    pub unique_rectangle: bool,
+    /// Remote pairs.
+    pub remote_pairs: bool,
+    /// X-Wing, with or without fins.
+    pub finned_x_wing: bool,
+    /// Bivalue universal grave plus one.
+    ///
+    /// Like the unique rectangle, this assumes the puzzle has a unique
+    /// solution.
+    pub bug_plus_one: bool,
+    /// Almost locked sets linked by a restricted common candidate, or
+    /// ALS-XZ.
+    ///
+    /// This is the slowest of the techniques, as it compares every pair of
+    /// almost locked sets on the board.
+    pub als_xz: bool,
+}
+
+/// One of the optional techniques.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Technique {
+    Claiming,
+    UniqueRectangle,
+    RemotePairs,
+    FinnedXWing,
+    BugPlusOne,
+    AlsXz,
+}
+
+impl Technique {
+    /// All techniques in the order the solver tries them.
+    pub const ALL: [Technique; 6] = [
+        Technique::Claiming,
+        Technique::UniqueRectangle,
+        Technique::RemotePairs,
+        Technique::FinnedXWing,
+        Technique::BugPlusOne,
+        Technique::AlsXz,
+    ];
+
+    /// Applies this technique to `board`.
+    /// Returns true if any candidate was removed.
+    pub fn apply(self, board: &mut Board<SolvingCell>) -> bool {
+        eliminate(board, &self.find(board))
+    }
+
+    /// Finds the candidates that this technique would remove from `board`,
+    /// without removing them.
+    ///
+    /// The candidates are listed as `(row, col, value)` in ascending order,
+    /// each only once.
+    pub fn find(self, board: &Board<SolvingCell>) -> Vec<(usize, usize, usize)> {
+        match self {
+            Technique::Claiming => find_claiming_reduction(board),
+            Technique::UniqueRectangle => find_unique_rectangle(board),
+            Technique::RemotePairs => find_remote_pairs(board),
+            Technique::FinnedXWing => find_finned_x_wing(board),
+            Technique::BugPlusOne => find_bug_plus_one(board),
+            Technique::AlsXz => find_als_xz(board),
+        }
+    }
+}
+
+/// Prints the usual name of the technique, such as `X-Wing`.
+impl Display for Technique {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Technique::Claiming => "claiming",
+            Technique::UniqueRectangle => "unique rectangle",
+            Technique::RemotePairs => "remote pairs",
+            Technique::FinnedXWing => "X-Wing",
+            Technique::BugPlusOne => "BUG+1",
+            Technique::AlsXz => "ALS-XZ",
+        })
+    }
+}
+
+impl Techniques {
+    /// Whether `technique` is enabled.
+    pub fn contains(&self, technique: Technique) -> bool {
+        match technique {
+            Technique::Claiming => self.claiming,
+            Technique::UniqueRectangle => self.unique_rectangle,
+            Technique::RemotePairs => self.remote_pairs,
+            Technique::FinnedXWing => self.finned_x_wing,
+            Technique::BugPlusOne => self.bug_plus_one,
+            Technique::AlsXz => self.als_xz,
+        }
+    }
+
+    /// Iterates the enabled techniques in the order the solver tries them.
+    pub fn iter(&self) -> impl Iterator<Item = Technique> + '_ {
+        IntoIterator::into_iter(Technique::ALL).filter(move |&t| self.contains(t))
+    }
+
+    /// Applies the enabled techniques to `board`.
+    /// Returns true if any candidate was removed.
+    pub(crate) fn apply(&self, board: &mut Board<SolvingCell>) -> bool {
+        let mut has_update = false;
+        for technique in self.iter() {
+            #[cfg(test)]
+            let consistent = allows_known_solution(board);
+            has_update |= technique.apply(board);
+            #[cfg(test)]
+            if consistent {
+                check_known_solution(technique, board);
+            }
+        }
+        has_update
+    }
+}
+
+/// Collects techniques into the set that enables exactly them.
+impl FromIterator<Technique> for Techniques {
+    fn from_iter<I: IntoIterator<Item = Technique>>(iter: I) -> Techniques {
+        let mut techniques = Techniques::default();
+        for technique in iter {
+            match technique {
+                Technique::Claiming => techniques.claiming = true,
+                Technique::UniqueRectangle => techniques.unique_rectangle = true,
+                Technique::RemotePairs => techniques.remote_pairs = true,
+                Technique::FinnedXWing => techniques.finned_x_wing = true,
+                Technique::BugPlusOne => techniques.bug_plus_one = true,
+                Technique::AlsXz => techniques.als_xz = true,
+            }
+        }
+        techniques
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    /// Solution that the techniques are checked against in tests, if set by
+    /// [`with_known_solution`].
+    static KNOWN_SOLUTION: std::cell::Cell<Option<Board<usize>>> =
+        const { std::cell::Cell::new(None) };
+}
+
+/// Runs `f`, checking that no technique removes a value of `solution` from
+/// a board that still allows it. This catches a technique that eliminates
+/// too much, which otherwise would only show up as a missing solution.
+#[cfg(all(test, not(any(board_4x4, board_6x6, board_16x16, board_25x25))))]
+pub(crate) fn with_known_solution<R, F: FnOnce() -> R>(solution: &Board<usize>, f: F) -> R {
+    KNOWN_SOLUTION.with(|known| known.set(Some(*solution)));
+    let result = f();
+    KNOWN_SOLUTION.with(|known| known.set(None));
+    result
+}
+
+/// Whether `board` allows the known solution, if any.
+#[cfg(test)]
+fn allows_known_solution(board: &Board<SolvingCell>) -> bool {
+    KNOWN_SOLUTION
+        .with(|known| known.get())
+        .is_some_and(|solution| {
+            (0..N * N).all(|k| board.0[k / N][k % N].can_be(solution.0[k / N][k % N]))
+        })
+}
+
+/// Panics if `technique` has removed a value of the known solution.
+#[cfg(test)]
+fn check_known_solution(technique: Technique, board: &Board<SolvingCell>) {
+    if let Some(solution) = KNOWN_SOLUTION.with(|known| known.get()) {
+        for (i, j) in (0..N).flat_map(|i| (0..N).map(move |j| (i, j))) {
+            let n = solution.0[i][j];
+            assert!(
+                board.0[i][j].can_be(n),
+                "{:?} removed {} from r{}c{}, which is in the solution",
+                technique,
+                n + 1,
+                i + 1,
+                j + 1
+            );
+        }
+    }
+}
+
+/// Removes the candidates listed as `(row, col, value)` from `board`.
+/// Returns true if any candidate was removed.
+///
+/// This applies the result of [`Technique::find`] or one of the `find_*`
+/// functions.
+pub fn eliminate(board: &mut Board<SolvingCell>, eliminations: &[(usize, usize, usize)]) -> bool {
+    let mut has_update = false;
+    for &(i, j, n) in eliminations {
+        has_update |= board.0[i][j].remove(n);
+    }
+    has_update
+}
+
+/// Sorts the eliminations and drops duplicates.
+fn normalized(mut eliminations: Vec<(usize, usize, usize)>) -> Vec<(usize, usize, usize)> {
+    eliminations.sort_unstable();
+    eliminations.dedup();
+    eliminations
+}
+
+/// Locked candidates type 2, also known as claiming.
+///
+/// If a value can only be in one block within a row or column, it must be
+/// in the intersection of the two, so it cannot be anywhere else in the
+/// block. Returns true if any candidate was removed.
+pub fn claiming_reduction(board: &mut Board<SolvingCell>) -> bool {
+    eliminate(board, &find_claiming_reduction(board))
+}
+
+/// Finds the candidates that [`claiming_reduction`] would remove.
+pub fn find_claiming_reduction(board: &Board<SolvingCell>) -> Vec<(usize, usize, usize)> {
+    let mut eliminations = Vec::new();
+    for n in 0..N {
+        for k in 0..N {
+            claim(board, n, row_iter(k), |i, _| i == k, &mut eliminations);
+            claim(board, n, col_iter(k), |_, j| j == k, &mut eliminations);
+        }
+    }
+    normalized(eliminations)
+}
+
+/// Finds `n` in the block that has all of `line`'s candidates for `n`,
+/// except in the cells of the line, which `in_line` tells.
+fn claim<I, F>(
+    board: &Board<SolvingCell>,
+    n: usize,
+    line: I,
+    in_line: F,
+    eliminations: &mut Vec<(usize, usize, usize)>,
+) where
+    I: Iterator<Item = (usize, usize)>,
+    F: Fn(usize, usize) -> bool,
+{
+    let mut origins = line
+        .filter(|&(i, j)| board.0[i][j].can_be(n))
+        .map(|(i, j)| block_origin(i, j));
+    let origin = match origins.next() {
+        Some(origin) => origin,
+        None => return,
+    };
+    if origins.any(|o| o != origin) {
+        return;
+    }
+
+    for (i, j) in block_iter(origin.0, origin.1) {
+        if !in_line(i, j) && board.0[i][j].can_be(n) {
+            eliminations.push((i, j, n));
+        }
+    }
+}
+
+/// Unique rectangle type 1.
+///
+/// Consider four cells at the corners of a rectangle that spans two rows, two
+/// columns, and two blocks. If three of them have the same two candidates
+/// and nothing else, the fourth cell cannot take either candidate, since the
+/// two candidates could otherwise be swapped in the four cells, yielding a
+/// second solution. Returns true if any candidate was removed.
+pub fn unique_rectangle(board: &mut Board<SolvingCell>) -> bool {
+    eliminate(board, &find_unique_rectangle(board))
+}
+
+/// Finds the candidates that [`unique_rectangle`] would remove.
+pub fn find_unique_rectangle(board: &Board<SolvingCell>) -> Vec<(usize, usize, usize)> {
+    let mut eliminations = Vec::new();
+    for i1 in 0..N {
+        for i2 in i1 + 1..N {
+            for j1 in 0..N {
+                for j2 in j1 + 1..N {
+                    let same_band = i1 / BLOCK_ROWS == i2 / BLOCK_ROWS;
+                    let same_stack = j1 / BLOCK_COLS == j2 / BLOCK_COLS;
+                    if same_band != same_stack {
+                        let corners = [(i1, j1), (i1, j2), (i2, j2), (i2, j1)];
+                        unique_rectangle_at(board, corners, &mut eliminations);
+                    }
+                }
+            }
+        }
+    }
+    normalized(eliminations)
+}
+
+fn unique_rectangle_at(
+    board: &Board<SolvingCell>,
+    corners: [(usize, usize); 4],
+    eliminations: &mut Vec<(usize, usize, usize)>,
+) {
+    let cells = corners.map(|(i, j)| board.0[i][j]);
+    for roof in 0..4 {
+        let floor: Vec<SolvingCell> = (1..4).map(|d| cells[(roof + d) % 4]).collect();
+        if floor[0].count() != 2 || floor.iter().any(|c| !c.iter().eq(floor[0].iter())) {
+            continue;
+        }
+
+        let (i, j) = corners[roof];
+        let extra = &board.0[i][j];
+        if extra.count() > 2 && floor[0].iter().all(|n| extra.can_be(n)) {
+            eliminations.extend(floor[0].iter().map(|n| (i, j, n)));
+            return;
+        }
+    }
+}
+
+/// Whether two cells share a row, a column, or a block.
+fn sees((i1, j1): (usize, usize), (i2, j2): (usize, usize)) -> bool {
+    i1 == i2 || j1 == j2 || Unit::block_of(i1, j1) == Unit::block_of(i2, j2)
+}
+
+/// Remote pairs.
+///
+/// Cells that have the same two candidates and nothing else, and see each
+/// other one after another, form a chain along which the two values
+/// alternate. A cell that sees two cells of a chain an odd number of links
+/// apart sees both values, so it cannot take either. Returns true if any
+/// candidate was removed.
+pub fn remote_pairs(board: &mut Board<SolvingCell>) -> bool {
+    eliminate(board, &find_remote_pairs(board))
+}
+
+/// Finds the candidates that [`remote_pairs`] would remove.
+pub fn find_remote_pairs(board: &Board<SolvingCell>) -> Vec<(usize, usize, usize)> {
+    let mut eliminations = Vec::new();
+    for x in 0..N {
+        for y in x + 1..N {
+            let pairs =
+                board.cells_where(|cell| cell.count() == 2 && cell.can_be(x) && cell.can_be(y));
+
+            // Color each chain with the two parities. A chain that cannot be
+            // colored has an odd cycle, which is a contradiction left for the
+            // solver to find.
+            let mut colors: Vec<Option<(usize, bool)>> = vec![None; pairs.len()];
+            let mut chains = Vec::new();
+            for start in 0..pairs.len() {
+                if colors[start].is_some() {
+                    continue;
+                }
+                let chain = chains.len();
+                chains.push(true);
+                colors[start] = Some((chain, false));
+                let mut stack = vec![start];
+                while let Some(a) = stack.pop() {
+                    let parity = colors[a].unwrap().1;
+                    for b in 0..pairs.len() {
+                        if b == a || !sees(pairs[a], pairs[b]) {
+                            continue;
+                        }
+                        match colors[b] {
+                            None => {
+                                colors[b] = Some((chain, !parity));
+                                stack.push(b);
+                            }
+                            Some((_, p)) if p == parity => chains[chain] = false,
+                            Some(_) => (),
+                        }
+                    }
+                }
+            }
+
+            for i in 0..N {
+                for j in 0..N {
+                    if pairs.contains(&(i, j)) {
+                        continue;
+                    }
+                    let cell = &board.0[i][j];
+                    if !cell.can_be(x) && !cell.can_be(y) {
+                        continue;
+                    }
+                    let sees_parity = |chain, parity| {
+                        pairs
+                            .iter()
+                            .zip(&colors)
+                            .any(|(&p, &c)| c == Some((chain, parity)) && sees((i, j), p))
+                    };
+                    let eliminates = (0..chains.len()).any(|chain| {
+                        chains[chain] && sees_parity(chain, false) && sees_parity(chain, true)
+                    });
+                    if eliminates {
+                        eliminations.extend(
+                            IntoIterator::into_iter([x, y])
+                                .filter(|&n| cell.can_be(n))
+                                .map(|n| (i, j, n)),
+                        );
+                    }
+                }
+            }
+        }
+    }
+    normalized(eliminations)
+}
+
+/// X-Wing, with or without fins.
+///
+/// If a value can only be in the same two columns in each of two rows, it
+/// must be in those columns in those rows, so it cannot be anywhere else in
+/// the two columns. The same goes with rows and columns swapped.
+///
+/// If one of the rows also has the value in some other cells, the fins, that
+/// are all in one block, either a fin or the X-Wing holds. Then the value can
+/// still be removed from the cells that see both the fins and the X-Wing,
+/// which are the cells of the fins' block in one of the two columns.
+///
+/// Returns true if any candidate was removed.
+pub fn finned_x_wing(board: &mut Board<SolvingCell>) -> bool {
+    eliminate(board, &find_finned_x_wing(board))
+}
+
+/// Finds the candidates that [`finned_x_wing`] would remove.
+pub fn find_finned_x_wing(board: &Board<SolvingCell>) -> Vec<(usize, usize, usize)> {
+    let mut eliminations = Vec::new();
+    for transposed in [false, true] {
+        // Row `i` and column `j`, or the other way around if transposed.
+        let at = |i: usize, j: usize| if transposed { (j, i) } else { (i, j) };
+        // Block sizes across and along the lines.
+        let (across, along) = if transposed {
+            (BLOCK_COLS, BLOCK_ROWS)
+        } else {
+            (BLOCK_ROWS, BLOCK_COLS)
+        };
+        for n in 0..N {
+            let positions = |board: &Board<SolvingCell>, i: usize| -> Vec<usize> {
+                (0..N)
+                    .filter(|&j| {
+                        let (i, j) = at(i, j);
+                        board.0[i][j].can_be(n)
+                    })
+                    .collect()
+            };
+            for base in 0..N {
+                let columns = positions(board, base);
+                if columns.len() != 2 {
+                    continue;
+                }
+                for other in 0..N {
+                    if other == base {
+                        continue;
+                    }
+                    let others = positions(board, other);
+                    let fins: Vec<usize> = others
+                        .iter()
+                        .copied()
+                        .filter(|j| !columns.contains(j))
+                        .collect();
+
+                    // The rows from which `n` can be removed in each column.
+                    let rows: Vec<usize> = if fins.is_empty() {
+                        if others != columns {
+                            continue;
+                        }
+                        (0..N).collect()
+                    } else {
+                        let stack = fins[0] / along;
+                        if fins.iter().any(|&j| j / along != stack) {
+                            continue;
+                        }
+                        let band = other / across * across;
+                        (band..band + across).collect()
+                    };
+                    for &j in &columns {
+                        if !fins.is_empty() && j / along != fins[0] / along {
+                            continue;
+                        }
+                        for &i in &rows {
+                            if i != base && i != other {
+                                let (i, j) = at(i, j);
+                                if board.0[i][j].can_be(n) {
+                                    eliminations.push((i, j, n));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    normalized(eliminations)
+}
+
+/// Bivalue universal grave plus one.
+///
+/// A board where every unsolved cell has exactly two candidates and each
+/// candidate appears exactly twice in each unit it appears in, the bivalue
+/// universal grave, has no solution or more than one. If all unsolved cells
+/// but one have two candidates and the other has three, that cell must take
+/// the candidate without which the board would be such a grave. Returns true
+/// if the cell was solved.
+pub fn bug_plus_one(board: &mut Board<SolvingCell>) -> bool {
+    eliminate(board, &find_bug_plus_one(board))
+}
+
+/// Finds the candidates that [`bug_plus_one`] would remove.
+pub fn find_bug_plus_one(board: &Board<SolvingCell>) -> Vec<(usize, usize, usize)> {
+    let mut extra = None;
+    for (i, j) in (0..N).flat_map(|i| (0..N).map(move |j| (i, j))) {
+        match board.0[i][j].count() {
+            0 => return Vec::new(),
+            1 | 2 => (),
+            3 if extra.is_none() => extra = Some((i, j)),
+            _ => return Vec::new(),
+        }
+    }
+    let (i, j) = match extra {
+        Some(cell) => cell,
+        None => return Vec::new(),
+    };
+
+    let candidates: Vec<usize> = board.0[i][j].iter().collect();
+    for &n in &candidates {
+        let mut grave = *board;
+        grave.0[i][j].remove(n);
+        if is_bivalue_universal_grave(&grave) {
+            return candidates
+                .iter()
+                .filter(|&&m| m != n)
+                .map(|&m| (i, j, m))
+                .collect();
+        }
+    }
+    Vec::new()
+}
+
+/// Whether each candidate of the unsolved cells appears exactly twice in
+/// each unit it appears in.
+fn is_bivalue_universal_grave(board: &Board<SolvingCell>) -> bool {
+    all_units().all(|unit| {
+        (0..N).all(|n| {
+            let count = unit
+                .cells()
+                .filter(|&(i, j)| board.0[i][j].count() > 1 && board.0[i][j].can_be(n))
+                .count();
+            count == 0 || count == 2
+        })
+    })
+}
+
+/// Number of words in a [`CellSet`].
+const CELL_WORDS: usize = (N * N).div_ceil(128);
+
+/// Set of cells, where bit `k % 128` of word `k / 128` is for cell
+/// `(k / N, k % N)`.
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+struct CellSet([u128; CELL_WORDS]);
+
+impl CellSet {
+    /// The set of all cells on the board.
+    fn all() -> CellSet {
+        let mut set = CellSet::default();
+        for k in 0..N * N {
+            set.insert((k / N, k % N));
+        }
+        set
+    }
+
+    fn insert(&mut self, (i, j): (usize, usize)) {
+        let k = i * N + j;
+        self.0[k / 128] |= 1 << (k % 128);
+    }
+
+    fn union(self, other: CellSet) -> CellSet {
+        CellSet(std::array::from_fn(|w| self.0[w] | other.0[w]))
+    }
+
+    fn intersect(self, other: CellSet) -> CellSet {
+        CellSet(std::array::from_fn(|w| self.0[w] & other.0[w]))
+    }
+
+    fn difference(self, other: CellSet) -> CellSet {
+        CellSet(std::array::from_fn(|w| self.0[w] & !other.0[w]))
+    }
+
+    fn is_empty(self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    /// Iterates the cells in row-major order.
+    fn iter(self) -> impl Iterator<Item = (usize, usize)> {
+        let mut words = self.0;
+        let mut w = 0;
+        std::iter::from_fn(move || {
+            while w < CELL_WORDS && words[w] == 0 {
+                w += 1;
+            }
+            let word = words.get_mut(w)?;
+            let k = w * 128 + word.trailing_zeros() as usize;
+            *word &= *word - 1;
+            Some((k / N, k % N))
+        })
+    }
+}
+
+/// Almost locked set: unsolved cells in a unit that have one candidate more
+/// than cells between them.
+struct AlmostLockedSet {
+    cells: CellSet,
+    /// Candidates of the cells.
+    values: SolvingCell,
+    /// Cells that can be each value.
+    holders: [CellSet; N],
+}
+
+/// Returns the almost locked sets of `board`, each only once even if its
+/// cells share more than one unit.
+///
+/// Every subset of the unsolved cells of each unit is tried, so this gets
+/// slow on a board larger than 9x9 with many unsolved cells.
+fn almost_locked_sets(board: &Board<SolvingCell>) -> Vec<AlmostLockedSet> {
+    let full = SolvingCell::new(None);
+    let mut sets = Vec::new();
+    for unit in all_units() {
+        let unsolved: Vec<(usize, usize)> = unit
+            .cells()
+            .filter(|&(i, j)| board.0[i][j].count() > 1)
+            .collect();
+        for subset in 1..1u64 << unsolved.len() {
+            let mut set = AlmostLockedSet {
+                cells: CellSet::default(),
+                values: full.difference(&full),
+                holders: [CellSet::default(); N],
+            };
+            for (k, &(i, j)) in unsolved.iter().enumerate() {
+                if subset & 1 << k != 0 {
+                    set.cells.insert((i, j));
+                    set.values = set.values.union(&board.0[i][j]);
+                    for n in board.0[i][j].iter() {
+                        set.holders[n].insert((i, j));
+                    }
+                }
+            }
+            if set.values.count() == subset.count_ones() as usize + 1 {
+                sets.push(set);
+            }
+        }
+    }
+    sets.sort_unstable_by_key(|set| set.cells);
+    sets.dedup_by_key(|set| set.cells);
+    sets
+}
+
+/// Almost locked sets XZ.
+///
+/// `n` cells in a unit with `n + 1` candidates between them are an almost
+/// locked set: removing any one candidate would leave exactly one for each
+/// cell. Take two disjoint almost locked sets that both have a candidate X,
+/// where every cell of either that can be X sees every such cell of the
+/// other. Only one of the sets can have X, so the other is locked without X.
+/// Then for another candidate Z of both, one of the sets must have Z, and a
+/// cell outside them that sees all their cells that can be Z cannot be Z.
+/// Returns true if any candidate was removed.
+pub fn als_xz(board: &mut Board<SolvingCell>) -> bool {
+    eliminate(board, &find_als_xz(board))
+}
+
+/// Finds the candidates that [`als_xz`] would remove.
+pub fn find_als_xz(board: &Board<SolvingCell>) -> Vec<(usize, usize, usize)> {
+    // `peers[i * N + j]` is the cells that see `(i, j)`.
+    let peers: Vec<CellSet> = (0..N * N)
+        .map(|k| {
+            let mut peers = CellSet::default();
+            for cell in CellSet::all().iter() {
+                if cell != (k / N, k % N) && sees(cell, (k / N, k % N)) {
+                    peers.insert(cell);
+                }
+            }
+            peers
+        })
+        .collect();
+    let seen_by_all = |cells: CellSet| {
+        cells.iter().fold(CellSet::all(), |seen, (i, j)| {
+            seen.intersect(peers[i * N + j])
+        })
+    };
+
+    let sets = almost_locked_sets(board);
+    let mut eliminations = Vec::new();
+    for (k, a) in sets.iter().enumerate() {
+        for b in &sets[k + 1..] {
+            let common = a.values.intersect(&b.values);
+            if !a.cells.intersect(b.cells).is_empty() || common.count() < 2 {
+                continue;
+            }
+            for x in common.iter() {
+                // X is a restricted common candidate.
+                if !b.holders[x]
+                    .difference(seen_by_all(a.holders[x]))
+                    .is_empty()
+                {
+                    continue;
+                }
+                for z in common.iter().filter(|&z| z != x) {
+                    let outside = seen_by_all(a.holders[z].union(b.holders[z]))
+                        .difference(a.cells.union(b.cells));
+                    for (i, j) in outside.iter() {
+                        if board.0[i][j].can_be(z) {
+                            eliminations.push((i, j, z));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    normalized(eliminations)
+}
+
+// The tests are written for the 9x9 board.
+#[cfg(all(test, not(any(board_4x4, board_6x6, board_16x16, board_25x25))))]
+mod tests {
+
+    use super::*;
+    use crate::format::parse_sdm;
+    use crate::generator::generate;
+    use crate::generator::Symmetry;
+    use crate::rng::Rng;
+    use crate::solver::*;
+
+    fn cell(values: &[usize]) -> SolvingCell {
+        let mut cell = SolvingCell::new(None);
+        for n in 0..N {
+            if !values.contains(&n) {
+                cell.remove(n);
+            }
+        }
+        cell.acknowledge();
+        cell
+    }
+
+    #[test]
+    fn unique_rectangle_type_1() {
+        let solution = Board([
+            [0, 1, 2, 3, 4, 5, 6, 7, 8],
+            [3, 4, 5, 6, 7, 8, 0, 1, 2],
+            [6, 7, 8, 0, 1, 2, 3, 4, 5],
+            [2, 0, 1, 5, 3, 4, 8, 6, 7],
+            [5, 3, 4, 8, 6, 7, 2, 0, 1],
+            [8, 6, 7, 2, 0, 1, 5, 3, 4],
+            [1, 2, 0, 4, 5, 3, 7, 8, 6],
+            [4, 5, 3, 7, 8, 6, 1, 2, 0],
+            [7, 8, 6, 1, 2, 0, 4, 5, 3],
+        ]);
+        let mut board = solution.map(|&n| cell(&[n]));
+        board.0[0][0] = cell(&[0, 3]);
+        board.0[0][3] = cell(&[0, 3]);
+        board.0[1][0] = cell(&[0, 3]);
+        board.0[1][3] = cell(&[0, 3, 6]);
+
+        let mut disabled = board;
+        assert!(!Techniques::default().apply(&mut disabled));
+        assert_eq!(disabled, board);
+
+        let techniques = Techniques {
+            unique_rectangle: true,
+            ..Techniques::default()
+        };
+        assert!(techniques.apply(&mut board));
+        assert_eq!(board.0[1][3].iter().collect::<Vec<_>>(), vec![6]);
+        assert_eq!(board.0[0][0].iter().collect::<Vec<_>>(), vec![0, 3]);
+        assert!(!techniques.apply(&mut board));
+    }
+
+    #[test]
+    fn unique_rectangle_needs_two_blocks() {
+        // The same pattern across two bands and two stacks spans four blocks.
+        let mut board = Board([[cell(&[8]); N]; N]);
+        board.0[0][0] = cell(&[0, 3]);
+        board.0[0][3] = cell(&[0, 3]);
+        board.0[3][0] = cell(&[0, 3]);
+        board.0[3][3] = cell(&[0, 3, 6]);
+        assert!(!unique_rectangle(&mut board));
+    }
+
+    #[test]
+    fn remote_pairs_chain() {
+        // (0, 0) - (0, 4) - (4, 4) - (4, 8) is a chain of four cells.
+        let mut board = Board([[cell(&[8]); N]; N]);
+        board.0[0][0] = cell(&[0, 3]);
+        board.0[0][4] = cell(&[0, 3]);
+        board.0[4][4] = cell(&[0, 3]);
+        board.0[4][8] = cell(&[0, 3]);
+        // These see both ends.
+        board.0[0][8] = cell(&[0, 3, 5]);
+        board.0[4][0] = cell(&[0, 5]);
+        // This sees only one end.
+        board.0[8][0] = cell(&[0, 3, 6]);
+
+        let techniques = Techniques {
+            remote_pairs: true,
+            ..Techniques::default()
+        };
+        assert!(techniques.apply(&mut board));
+        assert_eq!(board.0[0][8].iter().collect::<Vec<_>>(), vec![5]);
+        assert_eq!(board.0[4][0].iter().collect::<Vec<_>>(), vec![5]);
+        assert_eq!(board.0[8][0].iter().collect::<Vec<_>>(), vec![0, 3, 6]);
+        assert_eq!(board.0[0][0].iter().collect::<Vec<_>>(), vec![0, 3]);
+        assert!(!techniques.apply(&mut board));
+    }
+
+    #[test]
+    fn remote_pairs_solve_without_guessing() {
+        let problem = parse_sdm(
+            "217063008090000400006000007500000030002345000070020000100000780008006100000002090",
+        )
+        .unwrap()[0];
+        let stuck = solve_logically(&problem, &Techniques::default());
+        assert!(matches!(stuck, LogicalOutcome::Stuck(_)));
+
+        let techniques = Techniques {
+            remote_pairs: true,
+            ..Techniques::default()
+        };
+        assert_eq!(
+            solve_logically(&problem, &techniques),
+            LogicalOutcome::Solved(solve_first(&problem).unwrap())
+        );
+    }
+
+    #[test]
+    fn finned_x_wing_in_block_of_fins() {
+        let mut board = Board([[cell(&[8]); N]; N]);
+        // 0 is only in columns 1 and 6 in row 0, and in row 4 also in the
+        // fins (4, 7) and (4, 8).
+        for &(i, j) in &[(0, 1), (0, 6), (4, 1), (4, 6), (4, 7), (4, 8)] {
+            board.0[i][j] = cell(&[0, 5]);
+        }
+        for &(i, j) in &[(2, 1), (3, 6), (5, 6), (7, 6)] {
+            board.0[i][j] = cell(&[0, 2]);
+        }
+
+        let techniques = Techniques {
+            finned_x_wing: true,
+            ..Techniques::default()
+        };
+        assert!(techniques.apply(&mut board));
+        assert_eq!(board.0[3][6].iter().collect::<Vec<_>>(), vec![2]);
+        assert_eq!(board.0[5][6].iter().collect::<Vec<_>>(), vec![2]);
+        // These do not see the fins.
+        assert_eq!(board.0[7][6].iter().collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(board.0[2][1].iter().collect::<Vec<_>>(), vec![0, 2]);
+        assert!(!techniques.apply(&mut board));
+    }
+
+    #[test]
+    fn plain_x_wing_in_columns() {
+        let mut board = Board([[cell(&[8]); N]; N]);
+        for &(i, j) in &[(1, 2), (6, 2), (1, 5), (6, 5)] {
+            board.0[i][j] = cell(&[3, 4]);
+        }
+        board.0[1][0] = cell(&[3, 7]);
+        board.0[6][8] = cell(&[3, 7]);
+        board.0[2][0] = cell(&[3, 7]);
+        // Columns 5 and 0 also form an X-Wing with the fin (2, 0) and
+        // without (6, 0), which removes 3 from (1, 2).
+        assert_eq!(
+            find_finned_x_wing(&board),
+            vec![(1, 0, 3), (1, 2, 3), (6, 8, 3)]
+        );
+        assert!(finned_x_wing(&mut board));
+        assert_eq!(board.0[1][0].iter().collect::<Vec<_>>(), vec![7]);
+        assert_eq!(board.0[6][8].iter().collect::<Vec<_>>(), vec![7]);
+        assert_eq!(board.0[2][0].iter().collect::<Vec<_>>(), vec![3, 7]);
+    }
+
+    #[test]
+    fn finned_x_wing_solves_without_guessing() {
+        let problem = parse_sdm(
+            "000109000000030002080400907069070510000000000405000700000000090007504600896001000",
+        )
+        .unwrap()[0];
+        let stuck = solve_logically(&problem, &Techniques::default());
+        assert!(matches!(stuck, LogicalOutcome::Stuck(_)));
+
+        let techniques = Techniques {
+            finned_x_wing: true,
+            ..Techniques::default()
+        };
+        assert_eq!(
+            solve_logically(&problem, &techniques),
+            LogicalOutcome::Solved(solve_first(&problem).unwrap())
+        );
+    }
+
+    #[test]
+    fn bug_plus_one_solves_without_guessing() {
+        let problem = parse_sdm(
+            "031040800400097200050000090000100072000020000000408900070002056000700000500800040",
+        )
+        .unwrap()[0];
+        let stuck = solve_logically(&problem, &Techniques::default());
+        assert!(matches!(stuck, LogicalOutcome::Stuck(_)));
+
+        let techniques = Techniques {
+            bug_plus_one: true,
+            ..Techniques::default()
+        };
+        assert_eq!(
+            solve_logically(&problem, &techniques),
+            LogicalOutcome::Solved(solve_first(&problem).unwrap())
+        );
+    }
+
+    #[test]
+    fn bug_plus_one_needs_a_single_extra_cell() {
+        let mut board = Board([[cell(&[8]); N]; N]);
+        board.0[0][0] = cell(&[0, 1, 2]);
+        board.0[4][4] = cell(&[0, 1, 2]);
+        assert!(!bug_plus_one(&mut board));
+    }
+
+    #[test]
+    fn als_xz_through_a_restricted_candidate() {
+        // A bivalue cell in column 0 and two cells in row 4 are almost locked
+        // sets, which can only have 0 in one of r1c1 and r5c1, so one of them
+        // has 1 in r1c1 or r5c4.
+        let mut board = Board([[cell(&[8]); N]; N]);
+        board.0[0][0] = cell(&[0, 1]);
+        board.0[4][0] = cell(&[0, 2]);
+        board.0[4][3] = cell(&[1, 2]);
+        board.0[0][3] = cell(&[1, 5]);
+        assert_eq!(find_als_xz(&board), vec![(0, 3, 1)]);
+        assert!(als_xz(&mut board));
+        assert_eq!(board.0[0][3].iter().collect::<Vec<_>>(), vec![5]);
+        assert!(!als_xz(&mut board));
+
+        // Three cells with four candidates are not locked enough.
+        let mut board = Board([[cell(&[8]); N]; N]);
+        board.0[0][0] = cell(&[0, 1]);
+        board.0[4][0] = cell(&[0, 2]);
+        board.0[4][3] = cell(&[1, 2, 3]);
+        board.0[0][3] = cell(&[1, 5]);
+        assert_eq!(find_als_xz(&board), vec![]);
+    }
+
+    #[test]
+    fn als_xz_solves_without_guessing() {
+        let problem = parse_sdm(
+            ".1......3.....41...5...2.9.9.....6...6.8.5.1.4..6.9.2.8...57....4..3...5.9.....7.",
+        )
+        .unwrap()[0];
+        let stuck = solve_logically(&problem, &Techniques::default());
+        assert!(matches!(stuck, LogicalOutcome::Stuck(_)));
+
+        let techniques = Techniques {
+            als_xz: true,
+            ..Techniques::default()
+        };
+        assert_eq!(
+            solve_logically(&problem, &techniques),
+            LogicalOutcome::Solved(solve_first(&problem).unwrap())
+        );
+    }
+
+    #[test]
+    fn claiming_in_row_and_column() {
+        let all: Vec<usize> = (0..N).collect();
+        let all_but = |n: usize| cell(&all.iter().copied().filter(|&m| m != n).collect::<Vec<_>>());
+
+        // 0 is only in block (3, 3) within row 4.
+        let mut board = Board([[cell(&all); N]; N]);
+        for j in (0..3).chain(6..9) {
+            board.0[4][j] = all_but(0);
+        }
+        let techniques = Techniques {
+            claiming: true,
+            ..Techniques::default()
+        };
+        let eliminations = find_claiming_reduction(&board);
+        assert_eq!(eliminations.len(), 6);
+        assert!(eliminations.iter().all(|&(i, _, n)| i != 4 && n == 0));
+        assert!(techniques.apply(&mut board));
+        for (i, j) in block_iter(3, 3) {
+            assert_eq!(board.0[i][j].can_be(0), i == 4, "({}, {})", i, j);
+        }
+        assert!(board.0[3][2].can_be(0));
+        assert!(!techniques.apply(&mut board));
+
+        // 7 is only in block (0, 6) within column 8.
+        let mut board = Board([[cell(&all); N]; N]);
+        for i in 3..9 {
+            board.0[i][8] = all_but(7);
+        }
+        assert!(claiming_reduction(&mut board));
+        for (i, j) in block_iter(0, 6) {
+            assert_eq!(board.0[i][j].can_be(7), j == 8, "({}, {})", i, j);
+        }
+    }
+
+    #[test]
+    fn find_agrees_with_apply() {
+        let all = Techniques {
+            claiming: true,
+            unique_rectangle: true,
+            remote_pairs: true,
+            finned_x_wing: true,
+            bug_plus_one: true,
+            als_xz: true,
+        };
+        let mut found = 0;
+        for seed in 0..20 {
+            let problem = generate(22, Symmetry::None, &mut Rng::new(seed));
+            let board = match solve_logically(&problem, &Techniques::default()) {
+                LogicalOutcome::Stuck(board) => board,
+                _ => continue,
+            };
+            for technique in all.iter() {
+                let eliminations = technique.find(&board);
+                for &(i, j, n) in &eliminations {
+                    assert!(board.0[i][j].can_be(n), "{:?}", technique);
+                }
+                let mut applied = board;
+                assert_eq!(technique.apply(&mut applied), !eliminations.is_empty());
+                let removed = (0..N * N)
+                    .map(|k| (k / N, k % N))
+                    .flat_map(|(i, j)| {
+                        let after = applied.0[i][j];
+                        board.0[i][j]
+                            .iter()
+                            .filter(move |&n| !after.can_be(n))
+                            .map(move |n| (i, j, n))
+                    })
+                    .collect::<Vec<_>>();
+                assert_eq!(removed, eliminations, "{:?}", technique);
+                found += eliminations.len();
+            }
+        }
+        assert!(found > 0);
+    }
+
+    #[test]
+    fn techniques_keep_the_solution() {
+        let all = Techniques {
+            claiming: true,
+            unique_rectangle: true,
+            remote_pairs: true,
+            finned_x_wing: true,
+            bug_plus_one: true,
+            als_xz: true,
+        };
+        for seed in 0..20 {
+            let problem = generate(22, Symmetry::None, &mut Rng::new(seed));
+            let solution = solve_first(&problem).unwrap();
+            with_known_solution(&solution, || solve_logically(&problem, &all));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "FinnedXWing removed 1 from r1c1")]
+    fn over_elimination_is_caught() {
+        let solution = Board([[0; N]; N]);
+        let mut board = Board([[cell(&[0, 1]); N]; N]);
+        board.0[0][0] = cell(&[1]);
+        with_known_solution(&solution, || {
+            check_known_solution(Technique::FinnedXWing, &board);
+        });
+    }
+}
@@ -0,0 +1,107 @@
+//! Recording how the solver guesses.
+
+use std::fmt::Write;
+
+/// How the search went on at a node of a [`SearchTree`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    /// Propagation got stuck, so the children of the node are guesses.
+    Branch,
+    /// Propagation solved the board.
+    Solution,
+    /// Propagation left a cell without any candidate.
+    Contradiction,
+    /// A guess was needed, but it would have exceeded the depth limit.
+    DepthExceeded,
+}
+
+/// Node of a [`SearchTree`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Node {
+    /// Index of the parent node, which is `None` for the root.
+    pub parent: Option<usize>,
+    /// Row, column, and value of the guess that led to this node, all
+    /// 0-based. This is `None` for the root, which is the puzzle itself.
+    pub guess: Option<(usize, usize, usize)>,
+    pub outcome: Outcome,
+}
+
+/// Tree of the guesses made in solving a puzzle.
+///
+/// The root is at index 0, and every other node comes after its parent.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SearchTree {
+    pub nodes: Vec<Node>,
+}
+
+impl SearchTree {
+    /// Creates a tree with only the root.
+    pub(crate) fn new() -> Self {
+        SearchTree {
+            nodes: vec![Node {
+                parent: None,
+                guess: None,
+                outcome: Outcome::Branch,
+            }],
+        }
+    }
+
+    /// Renders the tree in the DOT language of Graphviz.
+    ///
+    /// Guesses are labeled like `r1c2=3` with 1-based numbers. Solutions are
+    /// filled in green, contradictions in red, and nodes cut off by the depth
+    /// limit in gray.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph search {\n");
+        for (k, node) in self.nodes.iter().enumerate() {
+            let label = match node.guess {
+                Some((i, j, n)) => format!("r{}c{}={}", i + 1, j + 1, n + 1),
+                None => "start".to_string(),
+            };
+            let style = match node.outcome {
+                Outcome::Branch => "",
+                Outcome::Solution => ", style=filled, fillcolor=palegreen",
+                Outcome::Contradiction => ", style=filled, fillcolor=lightpink",
+                Outcome::DepthExceeded => ", style=filled, fillcolor=lightgray",
+            };
+            writeln!(dot, "    n{} [label=\"{}\"{}];", k, label, style).unwrap();
+            if let Some(parent) = node.parent {
+                writeln!(dot, "    n{} -> n{};", parent, k).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn dot_of_tree() {
+        let mut tree = SearchTree::new();
+        tree.nodes.push(Node {
+            parent: Some(0),
+            guess: Some((0, 1, 2)),
+            outcome: Outcome::Contradiction,
+        });
+        tree.nodes.push(Node {
+            parent: Some(0),
+            guess: Some((0, 1, 3)),
+            outcome: Outcome::Solution,
+        });
+        assert_eq!(
+            tree.to_dot(),
+            "digraph search {
+    n0 [label=\"start\"];
+    n1 [label=\"r1c2=3\", style=filled, fillcolor=lightpink];
+    n0 -> n1;
+    n2 [label=\"r1c2=4\", style=filled, fillcolor=palegreen];
+    n0 -> n2;
+}
+"
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//! Checks that solving does not allocate, and that a reused
+//! [`Scratch`](sudoku_solver_in_rust::solver::Scratch) stops allocating
+//! once it has grown, so that solving many puzzles in a row does not
+//! allocate for each.
+//!
+//! These tests are built only for the 9x9 board.
+
+#![cfg(not(any(board_4x4, board_6x6, board_16x16, board_25x25)))]
+
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::cell::Cell;
+use sudoku_solver_in_rust::format::parse_sdm;
+use sudoku_solver_in_rust::solver::for_each_solution;
+use sudoku_solver_in_rust::solver::Scratch;
+
+/// Allocator that counts allocations made on each thread.
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+const FIXTURES: &str = include_str!("fixtures/regression.txt");
+
+#[test]
+fn solving_does_not_allocate() {
+    let boards = parse_sdm(FIXTURES).unwrap();
+    let before = ALLOCATIONS.with(Cell::get);
+    let mut solutions = 0;
+    for problem in boards.iter().step_by(2) {
+        for_each_solution(problem, |_| solutions += 1);
+    }
+    assert_eq!(ALLOCATIONS.with(Cell::get), before);
+    assert_eq!(solutions, boards.len() / 2);
+}
+
+#[test]
+fn reused_scratch_does_not_allocate() {
+    let boards = parse_sdm(FIXTURES).unwrap();
+    let mut scratch = Scratch::new();
+    let solve_all = |scratch: &mut Scratch| {
+        for pair in boards.chunks(2) {
+            let expected = pair[1].map(|cell| cell.unwrap());
+            assert_eq!(scratch.solve_first(&pair[0]), Some(expected));
+        }
+    };
+    solve_all(&mut scratch);
+    let before = ALLOCATIONS.with(Cell::get);
+    solve_all(&mut scratch);
+    assert_eq!(ALLOCATIONS.with(Cell::get), before);
+}
@@ -0,0 +1,98 @@
+//! Solves puzzles on a 4x4 board, so that the code that depends on the block
+//! size is checked on a size other than 9x9.
+//!
+//! These tests are built only with `RUSTFLAGS="--cfg board_4x4"`.
+
+#![cfg(board_4x4)]
+
+use sudoku_solver_in_rust::board::*;
+use sudoku_solver_in_rust::dlx::exact_solution_count;
+use sudoku_solver_in_rust::format::parse_sdm;
+use sudoku_solver_in_rust::format::read_problem;
+use sudoku_solver_in_rust::generator::generate;
+use sudoku_solver_in_rust::generator::Symmetry;
+use sudoku_solver_in_rust::rng::Rng;
+use sudoku_solver_in_rust::solver::*;
+use sudoku_solver_in_rust::techniques::Technique;
+
+/// Puzzles with their unique solutions.
+const PUZZLES: &str = "\
+....42..1......4
+3142423114232314
+...4.31..1......
+1234431221433421
+3.2.....4.3.2...
+3421124341322314
+";
+
+fn all_grids() -> Vec<Board<usize>> {
+    let mut grids = Vec::new();
+    for_each_solution(&Board::empty(), |grid| grids.push(grid));
+    grids
+}
+
+#[test]
+fn board_is_4x4() {
+    assert_eq!(N, 4);
+    assert_eq!(all_units().count(), 12);
+}
+
+#[test]
+fn known_puzzles() {
+    let boards = parse_sdm(PUZZLES).unwrap();
+    for pair in boards.chunks(2) {
+        let (problem, expected) = (&pair[0], pair[1].map(|cell| cell.unwrap()));
+        assert_eq!(solve_first(problem), Some(expected), "{:?}", problem);
+        assert!(is_uniquely_solvable(problem));
+    }
+
+    let problem = read_problem("3 0 2 0\n0 0 0 0\n4 0 3 0\n2 0 0 0\n".as_bytes()).unwrap();
+    assert_eq!(problem, boards[4]);
+}
+
+#[test]
+fn every_grid_is_found_once() {
+    let mut grids = all_grids();
+    assert_eq!(grids.len(), 288);
+    assert!(grids.iter().all(Board::is_valid_complete));
+    grids.sort();
+    grids.dedup();
+    assert_eq!(grids.len(), 288);
+    assert_eq!(exact_solution_count(&Board::empty()), 288);
+}
+
+#[test]
+fn every_clue_subset_of_a_grid() {
+    // All 2^16 puzzles made of some clues of a grid, counted by both solvers.
+    let grid = all_grids()[0];
+    for clues in 0..1u32 << (N * N) {
+        let mut problem = Board::empty();
+        for k in (0..N * N).filter(|k| clues & 1 << k != 0) {
+            problem.0[k / N][k % N] = Some(grid.0[k / N][k % N]);
+        }
+        let count = exact_solution_count(&problem);
+        assert_eq!(
+            count_solutions(&problem, 300) as u128,
+            count,
+            "{:?}",
+            problem
+        );
+    }
+}
+
+#[test]
+fn generated_puzzles() {
+    let all = Technique::ALL.iter().copied().collect();
+    for seed in 0..20 {
+        let problem = generate(0, Symmetry::None, &mut Rng::new(seed));
+        assert!(is_minimal(&problem), "{:?}", problem);
+        let solution = solve_first(&problem).unwrap();
+        match solve_logically(&problem, &all) {
+            LogicalOutcome::Solved(board) => assert_eq!(board, solution),
+            LogicalOutcome::Stuck(board) => {
+                assert!((0..N * N).all(|k| board.0[k / N][k % N].can_be(solution.0[k / N][k % N])))
+            }
+            LogicalOutcome::Contradiction(_) => panic!("{:?}", problem),
+        }
+    }
+}
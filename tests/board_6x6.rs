@@ -0,0 +1,159 @@
+//! Solves puzzles on a 6x6 board of blocks of two rows and three columns, so
+//! that the code that depends on the block shape is checked where the blocks
+//! are not square.
+//!
+//! These tests are built only with `RUSTFLAGS="--cfg board_6x6"`.
+
+#![cfg(board_6x6)]
+
+use sudoku_solver_in_rust::board::*;
+use sudoku_solver_in_rust::dlx::exact_solution_count;
+use sudoku_solver_in_rust::dlx::solve_dlx;
+use sudoku_solver_in_rust::format::format_candidates;
+use sudoku_solver_in_rust::format::parse_sdm;
+use sudoku_solver_in_rust::format::parse_sukaku;
+use sudoku_solver_in_rust::format::read_problem;
+use sudoku_solver_in_rust::generator::generate;
+use sudoku_solver_in_rust::generator::scramble;
+use sudoku_solver_in_rust::generator::Symmetry;
+use sudoku_solver_in_rust::rng::Rng;
+use sudoku_solver_in_rust::solver::*;
+use sudoku_solver_in_rust::techniques::Technique;
+
+/// Puzzles with their unique solutions.
+const PUZZLES: &str = "\
+6....55.26.....2.....16..4....32....
+614325532641461253253164146532325416
+...1..3.............23.54.5....1.2..
+254136361542536421142365425613613254
+41...5.....6.4.6..........23..5.....
+416235253416345621621543162354534162
+";
+
+/// The empty board with the first row filled in ascending order.
+fn first_row() -> Board<Option<usize>> {
+    let mut problem = Board::empty();
+    for j in 0..N {
+        problem.0[0][j] = Some(j);
+    }
+    problem
+}
+
+#[test]
+fn board_is_6x6() {
+    assert_eq!((BLOCK_ROWS, BLOCK_COLS, N), (2, 3, 6));
+    assert_eq!(all_units().count(), 18);
+    assert_eq!(
+        Unit::Block(1, 1).cells().collect::<Vec<_>>(),
+        [(2, 3), (2, 4), (2, 5), (3, 3), (3, 4), (3, 5)]
+    );
+}
+
+#[test]
+fn grid_count() {
+    // Relabeling the values maps the grids with any first row one to one
+    // onto those with the first row in order, so there are 6! times as many
+    // grids as these, 28200960 in all.
+    let problem = first_row();
+    assert_eq!(exact_solution_count(&problem), 39168);
+    let mut grids = Vec::new();
+    for_each_solution(&problem, |grid| grids.push(grid));
+    assert!(grids.iter().all(Board::is_valid_complete));
+    grids.sort();
+    grids.dedup();
+    assert_eq!(grids.len() * 720, 28200960);
+}
+
+#[test]
+fn known_puzzles() {
+    let boards = parse_sdm(PUZZLES).unwrap();
+    for pair in boards.chunks(2) {
+        let (problem, expected) = (&pair[0], pair[1].map(|cell| cell.unwrap()));
+        assert!(expected.is_valid_complete());
+        assert_eq!(solve_first(problem), Some(expected), "{:?}", problem);
+        assert!(is_uniquely_solvable(problem));
+        assert_eq!(exact_solution_count(problem), 1);
+    }
+
+    let problem = read_problem(
+        "6 0 0 0 0 5\n5 0 2 6 0 0\n0 0 0 2 0 0\n0 0 0 1 6 0\n0 4 0 0 0 0\n3 2 0 0 0 0\n".as_bytes(),
+    )
+    .unwrap();
+    assert_eq!(problem, boards[0]);
+}
+
+#[test]
+fn generated_and_scrambled_puzzles() {
+    let all = Technique::ALL.iter().copied().collect();
+    let mut rng = Rng::new(1);
+    for seed in 0..10 {
+        let problem = generate(0, Symmetry::None, &mut Rng::new(seed));
+        assert!(is_minimal(&problem), "{:?}", problem);
+        let solution = solve_first(&problem).unwrap();
+        assert!(solution.is_valid_complete());
+        match solve_logically(&problem, &all) {
+            LogicalOutcome::Solved(board) => assert_eq!(board, solution),
+            LogicalOutcome::Stuck(board) => {
+                assert!((0..N * N).all(|k| board.0[k / N][k % N].can_be(solution.0[k / N][k % N])))
+            }
+            LogicalOutcome::Contradiction(_) => panic!("{:?}", problem),
+        }
+
+        // Rectangular blocks cannot be transposed, so the scrambled puzzle
+        // must keep them rows by columns to stay uniquely solvable.
+        let scrambled = scramble(&problem, &mut rng);
+        assert_eq!(
+            scrambled.cells_where(Option::is_some).len(),
+            problem.cells_where(Option::is_some).len()
+        );
+        assert!(is_uniquely_solvable(&scrambled), "{:?}", scrambled);
+        assert!(solve_first(&scrambled).unwrap().is_valid_complete());
+    }
+}
+
+#[test]
+fn dlx_agrees_with_solver() {
+    let grid = solve_first(&first_row()).unwrap();
+    let mut rng = Rng::new(2);
+    for _ in 0..50 {
+        let mut problem = Board::empty();
+        for k in 0..N * N {
+            if rng.below(3) == 0 {
+                problem.0[k / N][k % N] = Some(grid.0[k / N][k % N]);
+            }
+        }
+        let count = exact_solution_count(&problem);
+        assert!(count >= 1);
+        assert_eq!(
+            count_solutions(&problem, 1000) as u128,
+            count.min(1000),
+            "{:?}",
+            problem
+        );
+        let mut first = None;
+        solve_dlx(&problem, |solution| {
+            first.get_or_insert(solution);
+        });
+        let solution = first.unwrap();
+        assert!(solution.is_valid_complete());
+        assert!((0..N * N)
+            .all(|k| problem.0[k / N][k % N].is_none_or(|n| n == solution.0[k / N][k % N])));
+    }
+}
+
+#[test]
+fn candidates_grid_has_rectangular_blocks() {
+    let problem = parse_sdm(PUZZLES).unwrap()[0];
+    let solution = solve_first(&problem).unwrap();
+    let text = format_candidates(&propagate(&solution.map(|&n| Some(n))));
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines.len(), N + N / BLOCK_ROWS - 1);
+    assert_eq!(lines[0], "6 1 4 | 3 2 5");
+    assert_eq!(lines[2], "------+------");
+    assert_eq!(lines[3], "4 6 1 | 2 5 3");
+
+    let board = problem.map(|&cell| SolvingCell::new(cell));
+    let parsed = parse_sukaku(&format_candidates(&board)).unwrap();
+    let values = |board: &Board<SolvingCell>| board.map(|cell| cell.iter().collect::<Vec<_>>());
+    assert_eq!(values(&parsed), values(&board));
+}
@@ -0,0 +1,92 @@
+//! Solves puzzles on a board larger than 9x9, so that the masks sized by the
+//! number of values and cells are checked where they no longer fit in the
+//! integers that suffice for 9x9.
+//!
+//! These tests are built only with `RUSTFLAGS="--cfg board_16x16"` or
+//! `RUSTFLAGS="--cfg board_25x25"`.
+
+#![cfg(any(board_16x16, board_25x25))]
+
+use sudoku_solver_in_rust::board::*;
+use sudoku_solver_in_rust::dlx::exact_solution_count;
+use sudoku_solver_in_rust::rng::Rng;
+use sudoku_solver_in_rust::solver::*;
+use sudoku_solver_in_rust::techniques::Technique;
+
+/// A valid complete grid made by shifting the rows of a block.
+fn pattern_grid() -> Board<usize> {
+    Board(std::array::from_fn(|i| {
+        std::array::from_fn(|j| (i * BLOCK_COLS + i / BLOCK_ROWS + j) % N)
+    }))
+}
+
+/// Returns the grid with `holes` cells chosen at random left empty.
+fn with_holes(grid: &Board<usize>, holes: usize, rng: &mut Rng) -> Board<Option<usize>> {
+    let mut problem = grid.map(|&value| Some(value));
+    let mut cells: Vec<usize> = (0..N * N).collect();
+    rng.shuffle(&mut cells);
+    for &k in &cells[..holes] {
+        problem.0[k / N][k % N] = None;
+    }
+    problem
+}
+
+#[test]
+#[allow(clippy::assertions_on_constants)]
+fn pattern_grid_is_valid() {
+    assert!(N > 9);
+    assert!(pattern_grid().is_valid_complete());
+}
+
+#[test]
+fn empty_cell_has_every_candidate() {
+    let cell = SolvingCell::new(None);
+    assert_eq!(cell.count(), N);
+    assert!(cell.can_be(N - 1));
+    assert_eq!(cell.iter().last(), Some(N - 1));
+    assert_eq!(
+        SolvingCell::new(Some(N - 1)).iter().collect::<Vec<_>>(),
+        [N - 1]
+    );
+}
+
+#[test]
+fn solving_with_holes() {
+    let grid = pattern_grid();
+    let mut rng = Rng::new(1);
+    for holes in [N, N * N / 4, N * N / 2] {
+        let problem = with_holes(&grid, holes, &mut rng);
+        let count = exact_solution_count(&problem);
+        assert!(count >= 1);
+        assert_eq!(
+            count_solutions(&problem, 2) as u128,
+            count.min(2),
+            "{:?}",
+            problem
+        );
+        let solution = solve_first(&problem).unwrap();
+        assert!(solution.is_valid_complete());
+        assert!(
+            (0..N * N)
+                .all(|k| problem.0[k / N][k % N]
+                    .is_none_or(|value| value == solution.0[k / N][k % N]))
+        );
+    }
+}
+
+#[test]
+fn techniques_on_nearly_solved_boards() {
+    let grid = pattern_grid();
+    let mut rng = Rng::new(2);
+    for technique in Technique::ALL {
+        let techniques = std::iter::once(technique).collect();
+        for _ in 0..3 {
+            let problem = with_holes(&grid, 2 * N, &mut rng);
+            match solve_logically(&problem, &techniques) {
+                LogicalOutcome::Solved(board) => assert!(board.is_valid_complete()),
+                LogicalOutcome::Stuck(_) => (),
+                LogicalOutcome::Contradiction(_) => panic!("{}: {:?}", technique, problem),
+            }
+        }
+    }
+}
@@ -0,0 +1,26 @@
+//! Solves the puzzles in `tests/fixtures/regression.txt` and checks the
+//! solutions against the expected ones.
+//!
+//! These tests are built only for the 9x9 board.
+
+#![cfg(not(any(board_4x4, board_6x6, board_16x16, board_25x25)))]
+
+use sudoku_solver_in_rust::format::parse_sdm;
+use sudoku_solver_in_rust::solver::count_solutions;
+use sudoku_solver_in_rust::solver::solve_first;
+
+const FIXTURES: &str = include_str!("fixtures/regression.txt");
+
+#[test]
+fn regression_corpus() {
+    let boards = parse_sdm(FIXTURES).unwrap();
+    assert!(!boards.is_empty());
+    assert_eq!(boards.len() % 2, 0, "a puzzle without a solution line");
+
+    for pair in boards.chunks(2) {
+        let (problem, expected) = (&pair[0], &pair[1]);
+        let expected = expected.map(|cell| cell.unwrap());
+        assert_eq!(solve_first(problem), Some(expected), "{:?}", problem);
+        assert_eq!(count_solutions(problem, 2), 1, "{:?}", problem);
+    }
+}
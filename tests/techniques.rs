@@ -0,0 +1,107 @@
+//! Checks that each optional technique is needed for some puzzle.
+//!
+//! Every technique has a fixture that the logical solver gets stuck on with
+//! all the other techniques, but solves once the technique is added. A new
+//! technique needs a new entry in `FIXTURES`.
+//!
+//! These tests are built only for the 9x9 board.
+
+#![cfg(not(any(board_4x4, board_6x6, board_16x16, board_25x25)))]
+
+use sudoku_solver_in_rust::board::*;
+use sudoku_solver_in_rust::format::parse_sdm;
+use sudoku_solver_in_rust::solver::solve_first;
+use sudoku_solver_in_rust::solver::solve_logically;
+use sudoku_solver_in_rust::solver::LogicalOutcome;
+use sudoku_solver_in_rust::techniques::Technique;
+use sudoku_solver_in_rust::techniques::Techniques;
+
+/// Puzzles that need each technique.
+const FIXTURES: &[(Technique, &str)] = &[
+    (
+        Technique::Claiming,
+        "...3.7.85...69.........519..7...43...3...9....2.8...5...6.7....3......1.4.7.8.2..",
+    ),
+    (
+        Technique::UniqueRectangle,
+        "..2..6.9889.3.........4...6.45.....1..36..7..7.............3...1...723...24...5.7",
+    ),
+    (
+        Technique::RemotePairs,
+        "..8.742..3........4.6..5.7...3..2547..7.5...2......8..9.4.2....8...........9...38",
+    ),
+    (
+        Technique::FinnedXWing,
+        ".5....94...........4...9.68.......2...2..3..7...8..4.17...2.5...193.....43..7.1..",
+    ),
+    (
+        Technique::BugPlusOne,
+        ".6...49......6..7.9..37.2...4.......7.....5435..2.....3......9.8.56..1...1.9.....",
+    ),
+    (
+        Technique::AlsXz,
+        ".1......3.....41...5...2.9.9.....6...6.8.5.1.4..6.9.2.8...57....4..3...5.9.....7.",
+    ),
+];
+
+/// Total number of candidates left in the outcome, which is smaller if the
+/// solver has made more progress.
+fn candidates(outcome: &LogicalOutcome) -> usize {
+    match outcome {
+        LogicalOutcome::Solved(_) => N * N,
+        LogicalOutcome::Stuck(board) => board.0.iter().flatten().map(|c| c.count()).sum(),
+        LogicalOutcome::Contradiction(board) => panic!("contradiction:\n{:?}", board),
+    }
+}
+
+fn fixture(technique: Technique) -> Board<Option<usize>> {
+    let (_, puzzle) = FIXTURES
+        .iter()
+        .find(|(t, _)| *t == technique)
+        .unwrap_or_else(|| panic!("no fixture for {:?}", technique));
+    parse_sdm(puzzle).unwrap().remove(0)
+}
+
+#[test]
+fn every_technique_has_fixture() {
+    for &technique in &Technique::ALL {
+        fixture(technique);
+    }
+    assert_eq!(FIXTURES.len(), Technique::ALL.len());
+}
+
+#[test]
+fn technique_is_needed() {
+    for &technique in &Technique::ALL {
+        let problem = fixture(technique);
+        let others = Technique::ALL.iter().copied().filter(|&t| t != technique);
+        let outcome = solve_logically(&problem, &others.collect());
+        assert!(
+            matches!(outcome, LogicalOutcome::Stuck(_)),
+            "{:?} is not needed",
+            technique
+        );
+
+        let all = Technique::ALL.iter().copied().collect();
+        match solve_logically(&problem, &all) {
+            LogicalOutcome::Solved(solution) => {
+                assert_eq!(Some(solution), solve_first(&problem), "{:?}", technique)
+            }
+            _ => panic!("{:?} does not complete the solution", technique),
+        }
+    }
+}
+
+#[test]
+fn technique_advances_alone() {
+    for &technique in &Technique::ALL {
+        let problem = fixture(technique);
+        let singles = solve_logically(&problem, &Techniques::default());
+        let alone = solve_logically(&problem, &std::iter::once(technique).collect());
+        assert!(
+            candidates(&alone) < candidates(&singles),
+            "{:?} makes no progress",
+            technique
+        );
+    }
+}